@@ -0,0 +1,452 @@
+//! Statistical helpers for inspecting plaintexts and ciphertexts.
+//!
+//! All functions here are independent of any machine. They only look at the letters A–Z, fold case and ignore any
+//! other characters.
+
+use std::sync::OnceLock;
+
+use crate::{
+    wiring::{StandardWiring, Wiring},
+    EnigmaChar, EnigmaResult,
+};
+
+/// The 50 most common English bigrams with their frequency in percent of all bigrams.
+///
+/// Taken from Peter Norvig's count over the Google Books corpus, "English Letter Frequency Counts: Mayzner Revisited" (2013).
+const ENGLISH_BIGRAMS: [(&str, f64); 50] = [
+    ("TH", 3.56),
+    ("HE", 3.07),
+    ("IN", 2.43),
+    ("ER", 2.05),
+    ("AN", 1.99),
+    ("RE", 1.85),
+    ("ON", 1.76),
+    ("AT", 1.49),
+    ("EN", 1.45),
+    ("ND", 1.35),
+    ("TI", 1.34),
+    ("ES", 1.34),
+    ("OR", 1.28),
+    ("TE", 1.20),
+    ("OF", 1.17),
+    ("ED", 1.17),
+    ("IS", 1.13),
+    ("IT", 1.12),
+    ("AL", 1.09),
+    ("AR", 1.07),
+    ("ST", 1.05),
+    ("TO", 1.04),
+    ("NT", 1.04),
+    ("NG", 0.95),
+    ("SE", 0.93),
+    ("HA", 0.93),
+    ("AS", 0.87),
+    ("OU", 0.87),
+    ("IO", 0.83),
+    ("LE", 0.83),
+    ("VE", 0.83),
+    ("CO", 0.79),
+    ("ME", 0.79),
+    ("DE", 0.76),
+    ("HI", 0.76),
+    ("RI", 0.73),
+    ("RO", 0.73),
+    ("IC", 0.70),
+    ("NE", 0.69),
+    ("EA", 0.69),
+    ("RA", 0.69),
+    ("CE", 0.65),
+    ("LI", 0.62),
+    ("CH", 0.60),
+    ("LL", 0.58),
+    ("BE", 0.58),
+    ("MA", 0.57),
+    ("SI", 0.55),
+    ("OM", 0.55),
+    ("UR", 0.54),
+];
+
+/// Frequency in percent assumed for bigrams missing from `ENGLISH_BIGRAMS`
+const BIGRAM_FLOOR: f64 = 0.01;
+
+/// Returns the base-10 logarithm of the probability of every bigram, built from `ENGLISH_BIGRAMS` on first use
+fn bigram_table() -> &'static [[f64; 26]; 26] {
+    static TABLE: OnceLock<[[f64; 26]; 26]> = OnceLock::new();
+
+    TABLE.get_or_init(|| {
+        let mut table = [[(BIGRAM_FLOOR / 100.0).log10(); 26]; 26];
+        for (bigram, freq) in ENGLISH_BIGRAMS.iter() {
+            let b = bigram.as_bytes();
+            table[(b[0] - b'A') as usize][(b[1] - b'A') as usize] = (freq / 100.0).log10();
+        }
+
+        table
+    })
+}
+
+/// Relative frequency of each letter A–Z in English text, in percent.
+///
+/// Taken from the Wikipedia article "Letter frequency", after Robert Lewand's "Cryptological Mathematics" (2000).
+pub const ENGLISH_FREQ: [f64; 26] = [
+    8.167, 1.492, 2.782, 4.253, 12.702, 2.228, 2.015, 6.094, 6.966, 0.153, 0.772, 4.025, 2.406,
+    6.749, 7.507, 1.929, 0.095, 5.987, 6.327, 9.056, 2.758, 0.978, 2.360, 0.150, 1.974, 0.074,
+];
+
+/// Relative frequency of each letter A–Z in German text, in percent.
+///
+/// Taken from the Wikipedia article "Letter frequency". The table there also counts "Ä", "Ö", "Ü" and "ß", which is why
+/// these values add up to slightly less than 100.
+pub const GERMAN_FREQ: [f64; 26] = [
+    6.516, 1.886, 2.732, 5.076, 16.396, 1.656, 3.009, 4.577, 6.550, 0.268, 1.417, 3.437, 2.534,
+    9.776, 2.594, 0.670, 0.018, 7.003, 7.270, 6.154, 4.166, 0.846, 1.921, 0.034, 0.039, 1.134,
+];
+
+/// Returns how many times each letter of the alphabet occurs in the text, case folded
+///
+/// # Arguments
+/// * `text` - Text to count
+fn letter_counts(text: &str) -> [usize; 26] {
+    let mut counts = [0usize; 26];
+
+    for c in text.chars().filter(char::is_ascii_alphabetic) {
+        counts[(c.to_ascii_uppercase() as u8 - b'A') as usize] += 1;
+    }
+
+    counts
+}
+
+/// Returns the Shannon entropy of the letter distribution of the text, in bits per symbol.
+///
+/// The result ranges from 0 for text consisting of a single repeated letter (or no letters at all) to log2(26) ≈ 4.70
+/// for text where every letter is equally frequent. Enigma ciphertext comes close to the maximum, while natural
+/// language plaintext scores noticeably lower.
+///
+/// # Arguments
+/// * `text` - Text to measure
+///
+/// # Examples
+///
+/// ```
+/// use enigma::analysis::shannon_entropy;
+///
+/// assert_eq!(shannon_entropy("AAAA"), 0.0);
+/// assert!((shannon_entropy("ABCDEFGHIJKLMNOPQRSTUVWXYZ") - 26f64.log2()).abs() < 1e-9);
+/// ```
+pub fn shannon_entropy(text: &str) -> f64 {
+    let counts = letter_counts(text);
+    let total: usize = counts.iter().sum();
+
+    if total == 0 {
+        return 0.0;
+    }
+
+    counts
+        .iter()
+        .filter(|n| **n != 0)
+        .map(|n| {
+            let p = *n as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Returns the chi-squared statistic of the letter distribution of the text against an expected distribution.
+///
+/// The expected frequencies are relative, so they don't have to add up to 1 or 100, and letters with an expected
+/// frequency of 0 are skipped. Lower is closer to the expected distribution. Unlike the index of coincidence, this
+/// compares with a particular language, which keeps it informative on short messages. Returns 0 for text without
+/// letters.
+///
+/// # Arguments
+/// * `text` - Text to score
+/// * `expected` - Expected frequency of each letter A–Z, e.g. `ENGLISH_FREQ` or `GERMAN_FREQ`
+///
+/// # Examples
+///
+/// ```
+/// use enigma::analysis::{chi_squared, ENGLISH_FREQ};
+///
+/// assert!(chi_squared("the weather is nice", &ENGLISH_FREQ) < chi_squared("qxz jvk wpq fgzb a", &ENGLISH_FREQ));
+/// assert!((chi_squared("abc", &[1.0; 26]) - 23.0).abs() < 1e-9);
+/// ```
+pub fn chi_squared(text: &str, expected: &[f64; 26]) -> f64 {
+    let counts = letter_counts(text);
+    let total: usize = counts.iter().sum();
+    let scale: f64 = expected.iter().sum();
+
+    if total == 0 || scale <= 0.0 {
+        return 0.0;
+    }
+
+    counts
+        .iter()
+        .zip(expected.iter())
+        .filter(|(_, f)| **f > 0.0)
+        .map(|(n, f)| {
+            let e = total as f64 * f / scale;
+            (*n as f64 - e).powi(2) / e
+        })
+        .sum()
+}
+
+/// Returns how English-like the text is, based on its bigrams (pairs of consecutive letters).
+///
+/// The score is the mean base-10 logarithm of the probability of each bigram, using a table of the 50 most common
+/// English bigrams from Peter Norvig's count over the Google Books corpus. Bigrams missing from the table are assumed
+/// to have a frequency of 0.01 %. Higher (closer to zero) is more English-like. Only letters are considered, so
+/// bigrams span word boundaries. Returns `f64::NEG_INFINITY` for text with fewer than two letters.
+///
+/// This is cheaper but coarser than quadgram scoring, which makes it suitable as a first pass of a search.
+///
+/// # Arguments
+/// * `text` - Text to score
+///
+/// # Examples
+///
+/// ```
+/// use enigma::analysis::bigram_score;
+///
+/// assert!(bigram_score("the weather is nice") > bigram_score("qxz jvk wpq fgzb"));
+/// ```
+pub fn bigram_score(text: &str) -> f64 {
+    let table = bigram_table();
+    let letters: Vec<usize> = text
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| (c.to_ascii_uppercase() as u8 - b'A') as usize)
+        .collect();
+
+    if letters.len() < 2 {
+        return f64::NEG_INFINITY;
+    }
+
+    letters.windows(2).map(|w| table[w[0]][w[1]]).sum::<f64>() / (letters.len() - 1) as f64
+}
+
+/// Returns the standard rotors whose set of turnover notches is exactly the provided set of letters.
+///
+/// Letters are case folded and may repeat. Reflectors and the Greek rotors of the M4, which never step, are never returned. This is useful for identifying the rotor
+/// in the fast position from observed turnovers of its neighbour.
+///
+/// # Arguments
+/// * `notch_letters` - Positions at which a turnover was observed
+///
+/// # Examples
+///
+/// ```
+/// use enigma::{analysis::rotors_matching_notches, wiring::StandardWiring};
+///
+/// assert_eq!(rotors_matching_notches(&['V']), vec![StandardWiring::III]);
+/// assert_eq!(
+///     rotors_matching_notches(&['M', 'z']),
+///     vec![StandardWiring::VI, StandardWiring::VII, StandardWiring::VIII]
+/// );
+/// ```
+pub fn rotors_matching_notches(notch_letters: &[char]) -> Vec<StandardWiring> {
+    let mut observed: Vec<char> = notch_letters
+        .iter()
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    observed.sort_unstable();
+    observed.dedup();
+
+    StandardWiring::ALL
+        .into_iter()
+        .filter(|w| !w.is_reflector() && !w.is_greek())
+        .filter(|w| {
+            let wiring = Wiring::from(*w);
+            let notches: Vec<char> = wiring.notches.iter().map(|n| (b'A' + n) as char).collect();
+
+            notches == observed
+        })
+        .collect()
+}
+
+/// Returns every ring setting and position of a rotor that behaves the same on the first keypress as the given ones.
+///
+/// The ring setting turns the wiring against the letters and the notch, so a rotor encodes the same for all pairs with
+/// the same difference `position - ring`, the Grundstellung and Ringstellung shifted by the same amount. The notch
+/// stays with the letters, so of those the pairs are kept that are on a notch exactly if the start is, and step the
+/// rotor to the left on the first keypress the same way. The result holds `(ring, position)` pairs as uppercase letters,
+/// the start included, in the order of the ring setting.
+///
+/// # Arguments
+/// * `rotor` - Rotor to look at
+/// * `ring` - Ring setting to start from, case insensitive
+/// * `position` - Position to start from, case insensitive
+///
+/// # Examples
+///
+/// ```
+/// use enigma::{analysis::equivalent_ring_positions, wiring::StandardWiring};
+///
+/// let pairs = equivalent_ring_positions(StandardWiring::I, 'A', 'Q').unwrap();
+///
+/// assert_eq!(pairs, vec![('A', 'Q')]);
+/// assert_eq!(equivalent_ring_positions(StandardWiring::I, 'b', 'c').unwrap().len(), 25);
+/// ```
+pub fn equivalent_ring_positions(
+    rotor: StandardWiring,
+    ring: char,
+    position: char,
+) -> EnigmaResult<Vec<(char, char)>> {
+    let ring = EnigmaChar::try_from(ring)?.internal;
+    let position = EnigmaChar::try_from(position)?.internal;
+
+    let letter = |internal: u8| {
+        char::from(EnigmaChar {
+            internal,
+            uppercase: true,
+        })
+    };
+
+    let notches = Wiring::from(rotor).notches;
+    let offset = (position + 26 - ring) % 26;
+    let on_notch = notches.contains(&position);
+
+    Ok((0..26u8)
+        .map(|r| (r, (r + offset) % 26))
+        .filter(|(_, p)| notches.contains(p) == on_notch)
+        .map(|(r, p)| (letter(r), letter(p)))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_letter_counts() {
+        let counts = letter_counts("Aa b-Z!");
+
+        assert_eq!(counts[0], 2);
+        assert_eq!(counts[1], 1);
+        assert_eq!(counts[25], 1);
+        assert_eq!(counts.iter().sum::<usize>(), 4);
+    }
+
+    #[test]
+    fn test_shannon_entropy() {
+        assert_eq!(shannon_entropy(""), 0.0);
+        assert_eq!(shannon_entropy("123 !?"), 0.0);
+        assert!((shannon_entropy("abAB") - 1.0).abs() < 1e-9);
+
+        let plain = shannon_entropy("Feindliche Infanterie Kolonne beobachtet");
+        assert!(plain > 3.0 && plain < 26f64.log2());
+    }
+
+    #[test]
+    fn test_rotors_matching_notches() {
+        assert_eq!(rotors_matching_notches(&['q']), vec![StandardWiring::I]);
+        assert_eq!(
+            rotors_matching_notches(&['Z', 'Z', 'M']),
+            vec![
+                StandardWiring::VI,
+                StandardWiring::VII,
+                StandardWiring::VIII
+            ]
+        );
+        assert!(rotors_matching_notches(&['Z']).contains(&StandardWiring::V));
+        assert!(rotors_matching_notches(&['A']).is_empty());
+        assert!(rotors_matching_notches(&[]).is_empty());
+        assert!(rotors_matching_notches(&['Q', 'E']).is_empty());
+    }
+
+    #[test]
+    fn test_equivalent_ring_positions() {
+        use crate::{steckerbrett, Enigma, EnigmaError};
+
+        // Encodes every letter as the first keypress from the given ring setting and position of the right rotor
+        let first_step = |ring: char, position: char| {
+            let mut enigma = Enigma::standard(
+                StandardWiring::UKW_B,
+                StandardWiring::II,
+                StandardWiring::IV,
+                StandardWiring::I,
+                steckerbrett!("AQ FR").unwrap(),
+            )
+            .unwrap();
+            enigma.set_ring_str(&format!("AA{}", ring)).unwrap();
+
+            ('A'..='Z')
+                .map(|c| {
+                    enigma.set_position_str(&format!("AA{}", position)).unwrap();
+                    enigma.get_for_str(&c.to_string(), false, true).unwrap()
+                })
+                .collect::<String>()
+        };
+
+        let pairs = equivalent_ring_positions(StandardWiring::I, 'b', 'c').unwrap();
+        assert_eq!(pairs.len(), 25);
+        assert_eq!(pairs[0], ('A', 'B'));
+        assert!(pairs.contains(&('B', 'C')));
+        assert!(!pairs.contains(&('P', 'Q')));
+
+        let expected = first_step('B', 'C');
+        for (ring, position) in pairs {
+            assert_eq!(first_step(ring, position), expected);
+        }
+
+        // Same offset, but only one of them turns the middle rotor over
+        assert_ne!(first_step('P', 'Q'), expected);
+        assert_eq!(
+            equivalent_ring_positions(StandardWiring::I, 'P', 'Q').unwrap(),
+            vec![('P', 'Q')]
+        );
+
+        // Rotor VI turns over at M and Z, both of which have a pair with the offset of 'M' - 'A'
+        assert_eq!(
+            equivalent_ring_positions(StandardWiring::VI, 'A', 'm').unwrap(),
+            vec![('A', 'M'), ('N', 'Z')]
+        );
+        let pairs = equivalent_ring_positions(StandardWiring::VI, 'C', 'O').unwrap();
+        assert_eq!(pairs.len(), 24);
+        assert!(!pairs.contains(&('A', 'M')) && !pairs.contains(&('N', 'Z')));
+
+        assert!(matches!(
+            equivalent_ring_positions(StandardWiring::I, '1', 'A'),
+            Err(EnigmaError::InvalidChar('1'))
+        ));
+    }
+
+    #[test]
+    fn test_chi_squared() {
+        const GERMAN: &str = "Die Truppen halten ihre Stellungen gegen starke feindliche Angriffe.";
+
+        assert_eq!(chi_squared("", &GERMAN_FREQ), 0.0);
+        assert_eq!(chi_squared("!?", &GERMAN_FREQ), 0.0);
+        assert_eq!(
+            chi_squared("Ab-c", &ENGLISH_FREQ),
+            chi_squared("ABC", &ENGLISH_FREQ)
+        );
+        assert!((chi_squared("aazz", &[1.0; 26]) - 48.0).abs() < 1e-9);
+
+        // Letters picked by a fixed linear congruential generator, one for every letter of the sentence
+        let mut seed = 12345u32;
+        let random: String = GERMAN
+            .chars()
+            .filter(char::is_ascii_alphabetic)
+            .map(|_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                (b'A' + (seed >> 16) as u8 % 26) as char
+            })
+            .collect();
+
+        let german = chi_squared(GERMAN, &GERMAN_FREQ);
+        assert!(german * 3.0 < chi_squared(&random, &GERMAN_FREQ));
+        assert!(german < chi_squared(GERMAN, &ENGLISH_FREQ));
+    }
+
+    #[test]
+    fn test_bigram_score() {
+        assert_eq!(bigram_score("a"), f64::NEG_INFINITY);
+        assert!((bigram_score("th") - 0.0356f64.log10()).abs() < 1e-9);
+        assert!((bigram_score("T h!") - bigram_score("TH")).abs() < 1e-9);
+        assert!((bigram_score("QQ") - 0.0001f64.log10()).abs() < 1e-9);
+        assert!(std::ptr::eq(bigram_table(), bigram_table()));
+
+        let english = bigram_score("attack the enemy positions at dawn");
+        let random = bigram_score("vxqpl mwzkc jfyrb gdnhu sotei aqvxz");
+        assert!(english > random);
+    }
+}
@@ -0,0 +1,178 @@
+use alloc::{borrow::ToOwned, string::String};
+
+use crate::{wiring::StandardWiring, Enigma, EnigmaError, EnigmaResult, Steckerbrett};
+
+/// Builder for Enigma M3 machines, created by `Enigma::builder()`.
+///
+/// Nothing is validated until `build()` is called. Positions and rings default to "AAA" and the plugboard to no
+/// cables, while the reflector and the rotors have to be set.
+///
+/// # Examples
+///
+/// ```
+/// use enigma::{Enigma, wiring::StandardWiring};
+///
+/// let mut enigma = Enigma::builder()
+///     .reflector(StandardWiring::UKW_B)
+///     .rotors(StandardWiring::I, StandardWiring::II, StandardWiring::III)
+///     .rings("AAA")
+///     .positions("AAA")
+///     .plugboard("AQ FR SM")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!("olkr", enigma.get_for_str("test", false, true).unwrap());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EnigmaBuilder {
+    /// Wiring of the reflector
+    reflector: Option<StandardWiring>,
+    /// Wirings of the left, middle and right rotor
+    rotors: Option<[StandardWiring; 3]>,
+    /// Rotor positions, left to right
+    positions: Option<String>,
+    /// Ring settings, left to right
+    rings: Option<String>,
+    /// Plugboard cables, in the format accepted by `steckerbrett!()`
+    plugboard: Option<String>,
+}
+
+impl EnigmaBuilder {
+    /// Creates a new builder with nothing set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the reflector
+    ///
+    /// # Arguments
+    /// * `ukw` - Wiring of the reflector
+    pub fn reflector(mut self, ukw: StandardWiring) -> Self {
+        self.reflector = Some(ukw);
+        self
+    }
+
+    /// Sets the rotors
+    ///
+    /// # Arguments
+    /// * `left` - Wiring of the left rotor (rotor 1)
+    /// * `middle` - Wiring of the middle rotor (rotor 2)
+    /// * `right` - Wiring of the right rotor (rotor 3)
+    pub fn rotors(
+        mut self,
+        left: StandardWiring,
+        middle: StandardWiring,
+        right: StandardWiring,
+    ) -> Self {
+        self.rotors = Some([left, middle, right]);
+        self
+    }
+
+    /// Sets the rotor positions, in the format of `Enigma::set_position_str()`
+    ///
+    /// # Arguments
+    /// * `positions` - One letter per rotor, left to right
+    pub fn positions(mut self, positions: &str) -> Self {
+        self.positions = Some(positions.to_owned());
+        self
+    }
+
+    /// Sets the ring settings, in the format of `Enigma::set_ring_str()`
+    ///
+    /// # Arguments
+    /// * `rings` - One letter per rotor, left to right
+    pub fn rings(mut self, rings: &str) -> Self {
+        self.rings = Some(rings.to_owned());
+        self
+    }
+
+    /// Sets the plugboard cables, in the format accepted by `steckerbrett!()`, e.g. "AQ FR SM"
+    ///
+    /// # Arguments
+    /// * `plugboard` - Whitespace separated letter pairs
+    pub fn plugboard(mut self, plugboard: &str) -> Self {
+        self.plugboard = Some(plugboard.to_owned());
+        self
+    }
+
+    /// Builds the machine.
+    ///
+    /// # Errors
+    ///
+    /// * `EnigmaError::InvalidRotorSelection` - The reflector or the rotors were not set
+    /// * `EnigmaError::InvalidSteckerbrettString` - A letter is plugged to itself or more than once, or there are too many cables
+    /// * `EnigmaError::InvalidPositionLength` - Positions or rings don't have one letter per rotor
+    /// * `EnigmaError::InvalidChar` - Positions, rings or the plugboard contain a non-letter
+    pub fn build(&self) -> EnigmaResult<Enigma> {
+        let ukw = self
+            .reflector
+            .ok_or_else(|| EnigmaError::InvalidRotorSelection("missing reflector".to_owned()))?;
+        let [l, m, r] = self
+            .rotors
+            .ok_or_else(|| EnigmaError::InvalidRotorSelection("missing rotors".to_owned()))?;
+        let stecker = match &self.plugboard {
+            Some(plugboard) => Steckerbrett::try_from(plugboard.as_str())?,
+            None => Steckerbrett::new(),
+        };
+
+        let mut enigma = Enigma::standard(ukw, l, m, r, stecker)?;
+        enigma.set_ring_str(self.rings.as_deref().unwrap_or("AAA"))?;
+        enigma.set_position_str(self.positions.as_deref().unwrap_or("AAA"))?;
+
+        Ok(enigma)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let builder = EnigmaBuilder::new()
+            .reflector(StandardWiring::UKW_B)
+            .rotors(StandardWiring::I, StandardWiring::II, StandardWiring::III);
+
+        let enigma = builder.build().unwrap();
+        assert_eq!(enigma.get_position_str(), "AAA");
+        assert_eq!(enigma.get_ring_str(), "AAA");
+        assert!(enigma.config().plugboard.is_empty());
+
+        let enigma = builder
+            .clone()
+            .positions("FCB")
+            .rings("BUL")
+            .plugboard("AQ")
+            .build()
+            .unwrap();
+        assert_eq!(enigma.get_position_str(), "FCB");
+        assert_eq!(enigma.get_ring_str(), "BUL");
+        assert_eq!(enigma.config().plugboard, vec![('A', 'Q')]);
+
+        assert!(matches!(
+            EnigmaBuilder::new()
+                .reflector(StandardWiring::UKW_B)
+                .build(),
+            Err(EnigmaError::InvalidRotorSelection(_))
+        ));
+        assert!(matches!(
+            builder
+                .clone()
+                .rotors(StandardWiring::I, StandardWiring::II, StandardWiring::I)
+                .build(),
+            Err(EnigmaError::InvalidRotorSelection(_))
+        ));
+        assert!(matches!(
+            builder.clone().reflector(StandardWiring::IV).build(),
+            Err(EnigmaError::InvalidRotorSelection(_))
+        ));
+        assert!(matches!(
+            builder.clone().positions("FC").build(),
+            Err(EnigmaError::InvalidPositionLength(_, 3))
+        ));
+        assert!(matches!(
+            builder.clone().plugboard("AB AC").build(),
+            Err(EnigmaError::InvalidSteckerbrettString(_))
+        ));
+    }
+}
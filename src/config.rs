@@ -0,0 +1,193 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    wiring::{StandardWiring, Wiring},
+    Enigma, EnigmaChar, EnigmaError, EnigmaResult, Steckerbrett, Uhr,
+};
+
+/// Configuration of a single rotor or reflector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RotorConfig {
+    /// Standard wiring this rotor uses, or `None` for a custom wiring
+    pub name: Option<StandardWiring>,
+    /// Wiring as a 26-letter uppercase string, in the format of `Wiring::new()`'s template
+    pub wiring: String,
+    /// Turnover notches as uppercase letters
+    pub notches: Vec<char>,
+}
+
+impl From<&Wiring> for RotorConfig {
+    fn from(w: &Wiring) -> Self {
+        let letter = |internal: u8| {
+            char::from(EnigmaChar {
+                internal,
+                uppercase: true,
+            })
+        };
+
+        Self {
+            name: StandardWiring::identify(w),
+            wiring: w.to_string(),
+            notches: w.notches.iter().copied().map(letter).collect(),
+        }
+    }
+}
+
+impl TryFrom<&RotorConfig> for Wiring {
+    type Error = EnigmaError;
+
+    /// Builds the standard wiring if the configuration names one, or the custom wiring described by `wiring` and `notches` otherwise
+    fn try_from(config: &RotorConfig) -> Result<Self, Self::Error> {
+        if let Some(name) = config.name {
+            return Ok(name.into());
+        }
+
+        let template: [char; 26] = config
+            .wiring
+            .chars()
+            .collect::<Vec<char>>()
+            .try_into()
+            .map_err(|_| EnigmaError::InvalidWiringName(config.wiring.clone()))?;
+
+        Wiring::new(template, &config.notches)
+    }
+}
+
+/// Configuration of an Uhr
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UhrConfig {
+    /// Plugged letters, in the format of `Uhr::pairs()`
+    pub pairs: Vec<(char, char)>,
+    /// Position of the disc, 0 to 39
+    pub dial: u8,
+}
+
+impl From<&Uhr> for UhrConfig {
+    fn from(uhr: &Uhr) -> Self {
+        Self {
+            pairs: uhr.pairs(),
+            dial: uhr.dial(),
+        }
+    }
+}
+
+impl TryFrom<&UhrConfig> for Uhr {
+    type Error = EnigmaError;
+
+    fn try_from(config: &UhrConfig) -> Result<Self, Self::Error> {
+        Uhr::new(&config.pairs, config.dial)
+    }
+}
+
+/// Snapshot of the complete configuration of a machine, returned by `Enigma::config()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnigmaConfig {
+    /// Reflector
+    pub reflector: RotorConfig,
+    /// Rotors, left to right. The Greek rotor of an M4 machine comes first.
+    pub rotors: Vec<RotorConfig>,
+    /// Number of rotors on the left that never step, 1 for an M4 machine and 0 otherwise
+    pub fixed: usize,
+    /// Rotor positions, left to right
+    pub positions: String,
+    /// Ring settings, left to right
+    pub rings: String,
+    /// Plugboard cables, in the format of `Steckerbrett::pairs()`
+    pub plugboard: Vec<(char, char)>,
+    /// Uhr used in place of the plugboard cables, if any
+    pub uhr: Option<UhrConfig>,
+}
+
+impl From<&Enigma> for EnigmaConfig {
+    fn from(e: &Enigma) -> Self {
+        Self {
+            reflector: e.ukw.wiring().into(),
+            rotors: e.rotors.iter().map(|r| r.wiring().into()).collect(),
+            fixed: e.fixed,
+            positions: e.get_position_str(),
+            rings: e.get_ring_str(),
+            plugboard: e.steckerbrett.pairs(),
+            uhr: e.uhr.as_ref().map(UhrConfig::from),
+        }
+    }
+}
+
+impl TryFrom<&EnigmaConfig> for Enigma {
+    type Error = EnigmaError;
+
+    fn try_from(config: &EnigmaConfig) -> Result<Self, Self::Error> {
+        let rotors = config
+            .rotors
+            .iter()
+            .map(Wiring::try_from)
+            .collect::<EnigmaResult<Vec<Wiring>>>()?;
+
+        // At least three rotors have to step, like the ones of an M3
+        if rotors.len() < config.fixed + 3 {
+            return Err(EnigmaError::InvalidRotorSelection(format!(
+                "{} rotors, {} fixed",
+                rotors.len(),
+                config.fixed
+            )));
+        }
+
+        let mut enigma = Enigma::with_rotors(
+            Wiring::try_from(&config.reflector)?,
+            rotors,
+            Steckerbrett::validated(&config.plugboard)?,
+        )?;
+        enigma.fixed = config.fixed;
+        enigma.uhr = config.uhr.as_ref().map(Uhr::try_from).transpose()?;
+        enigma.set_ring_str(&config.rings)?;
+        enigma.set_position_str(&config.positions)?;
+
+        Ok(enigma)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rotor_config() {
+        let config = RotorConfig::from(&Wiring::from(StandardWiring::VI));
+
+        assert_eq!(config.name, Some(StandardWiring::VI));
+        assert_eq!(config.wiring, "JPGVOUMFYQBENHZRDKASXLICTW");
+        assert_eq!(config.notches, vec!['M', 'Z']);
+
+        let custom = Wiring {
+            notches: vec![2],
+            ..StandardWiring::I.into()
+        };
+        let config = RotorConfig::from(&custom);
+
+        assert_eq!(config.name, None);
+        assert_eq!(config.wiring, "EKMFLGDQVZNTOWYHXUSPAIBRCJ");
+        assert_eq!(config.notches, vec!['C']);
+        assert!(Wiring::try_from(&config).unwrap() == custom);
+
+        let short = RotorConfig {
+            wiring: "EKMF".to_owned(),
+            ..config.clone()
+        };
+        assert!(matches!(
+            Wiring::try_from(&short),
+            Err(EnigmaError::InvalidWiringName(_))
+        ));
+
+        let invalid = RotorConfig {
+            wiring: "#KMFLGDQVZNTOWYHXUSPAIBRCJ".to_owned(),
+            ..config
+        };
+        assert!(matches!(
+            Wiring::try_from(&invalid),
+            Err(EnigmaError::InvalidChar('#'))
+        ));
+    }
+}
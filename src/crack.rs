@@ -0,0 +1,187 @@
+//! Ciphertext-only attacks on partially known machine settings.
+//!
+//! Searches here try every remaining setting and rank the candidates by how much the decrypted text looks like
+//! language rather than noise.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{wiring::StandardWiring, Enigma, EnigmaResult, Steckerbrett};
+
+/// Returns the index of coincidence of a letter distribution
+///
+/// # Arguments
+/// * `counts` - Number of occurrences of each letter of the alphabet
+fn coincidence(counts: &[usize; 26]) -> f64 {
+    let total: usize = counts.iter().sum();
+
+    if total < 2 {
+        return 0.0;
+    }
+
+    let pairs: usize = counts.iter().map(|n| n * n.saturating_sub(1)).sum();
+    pairs as f64 / (total * (total - 1)) as f64
+}
+
+/// Returns the index of coincidence of the text, i.e. the probability that two letters picked at random are the same.
+///
+/// Only the letters A–Z are considered, case folded. Uniformly random text scores about 1/26 ≈ 0.038, English about
+/// 0.066 and German about 0.076. Enigma ciphertext is close to random, so a wrong key leaves the score low while the
+/// right one brings it up to the level of the language. Returns 0 for text with fewer than two letters.
+///
+/// # Arguments
+/// * `text` - Text to measure
+///
+/// # Examples
+///
+/// ```
+/// use enigma::crack::index_of_coincidence;
+///
+/// assert_eq!(index_of_coincidence("AAAA"), 1.0);
+/// assert_eq!(index_of_coincidence("ABCDEFGHIJKLMNOPQRSTUVWXYZ"), 0.0);
+/// ```
+pub fn index_of_coincidence(text: &str) -> f64 {
+    let mut counts = [0usize; 26];
+
+    for c in text.chars().filter(char::is_ascii_alphabetic) {
+        counts[(c.to_ascii_uppercase() as u8 - b'A') as usize] += 1;
+    }
+
+    coincidence(&counts)
+}
+
+/// Tries all 26³ start positions of a machine with known rotors, reflector and plugboard and returns the `top_n` best.
+///
+/// Every position is ranked by the index of coincidence of the text it decrypts to, highest first, and returned as
+/// the position string with its score. Ring settings are left at "AAA". Apart from the point at which a rotor steps its
+/// left neighbour, a different ring setting has the same effect as a different start position, so with the wrong rings
+/// the best position usually still decrypts most of the message. Characters outside of the alphabet are ignored.
+///
+/// All trials share one machine, which is set to the next position before each of them. Returns
+/// `EnigmaError::InvalidRotorSelection` if the rotors and reflector don't make up a valid machine.
+///
+/// # Arguments
+/// * `ciphertext` - Text to decrypt
+/// * `ukw` - Reflector of the machine
+/// * `rotors` - Left, middle and right rotor of the machine
+/// * `stecker` - Plugboard of the machine
+/// * `top_n` - Number of candidates to return
+///
+/// # Examples
+///
+/// ```
+/// use enigma::{crack::find_positions, steckerbrett, wiring::StandardWiring, Enigma};
+///
+/// let rotors = (StandardWiring::I, StandardWiring::II, StandardWiring::III);
+/// let stecker = steckerbrett!("AQ FR SM").unwrap();
+///
+/// let mut enigma = Enigma::standard(StandardWiring::UKW_B, rotors.0, rotors.1, rotors.2, stecker.clone()).unwrap();
+/// enigma.set_position_str("LUP").unwrap();
+/// let enc = enigma
+///     .get_for_str(
+///         "Das Oberkommando der Wehrmacht gibt bekannt: Im Osten halten die eigenen Truppen ihre Stellungen \
+///          gegen starke feindliche Angriffe. Im Westen keine besonderen Ereignisse.",
+///         false,
+///         false,
+///     )
+///     .unwrap();
+///
+/// let candidates = find_positions(&enc, StandardWiring::UKW_B, rotors, &stecker, 3).unwrap();
+/// assert_eq!(candidates.len(), 3);
+/// assert_eq!(candidates[0].0, "LUP");
+/// ```
+pub fn find_positions(
+    ciphertext: &str,
+    ukw: StandardWiring,
+    rotors: (StandardWiring, StandardWiring, StandardWiring),
+    stecker: &Steckerbrett,
+    top_n: usize,
+) -> EnigmaResult<Vec<(String, f64)>> {
+    let letters: Vec<char> = ciphertext
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .collect();
+
+    let mut enigma = Enigma::standard(ukw, rotors.0, rotors.1, rotors.2, stecker.clone())?;
+    let mut scores = Vec::with_capacity(26 * 26 * 26);
+
+    for position in 0..26 * 26 * 26 {
+        let position =
+            [position / 676, position / 26 % 26, position % 26].map(|p| (b'A' + p as u8) as char);
+        // Letters of the alphabet are always valid positions
+        enigma
+            .set_position(Some(position[0]), Some(position[1]), Some(position[2]))
+            .unwrap();
+
+        let mut counts = [0usize; 26];
+        let mut fast = enigma.prepare_fast();
+        for c in letters.iter() {
+            // Only letters of the alphabet are left, which always encode
+            counts[fast.encode(*c).unwrap().internal as usize] += 1;
+        }
+
+        scores.push((position, coincidence(&counts)));
+    }
+
+    // Stable, so equal scores stay in alphabetical order of the positions
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    Ok(scores
+        .into_iter()
+        .take(top_n)
+        .map(|(position, score)| (position.iter().collect(), score))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::steckerbrett;
+
+    #[test]
+    fn test_index_of_coincidence() {
+        assert_eq!(index_of_coincidence(""), 0.0);
+        assert_eq!(index_of_coincidence("a"), 0.0);
+        assert_eq!(index_of_coincidence("aA"), 1.0);
+        assert_eq!(index_of_coincidence("A b!"), 0.0);
+        assert!((index_of_coincidence("AABB") - 1.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_find_positions() {
+        const PLAIN: &str = "An den Befehlshaber der Unterseeboote. Feindlicher Geleitzug in Quadrat Dora \
+            gesichtet, Kurs Nordost, Geschwindigkeit zehn Seemeilen. Boote sollen sofort angreifen und \
+            Fuehlung halten bis zum Eintreffen weiterer Einheiten.";
+
+        let rotors = (StandardWiring::IV, StandardWiring::II, StandardWiring::V);
+        let stecker = steckerbrett!("AV BS CG DL FU HZ IN KM OW RX").unwrap();
+
+        let mut enigma = Enigma::standard(
+            StandardWiring::UKW_B,
+            rotors.0,
+            rotors.1,
+            rotors.2,
+            stecker.clone(),
+        )
+        .unwrap();
+        enigma.set_position_str("QEV").unwrap();
+        let enc = enigma.get_for_str(PLAIN, true, true).unwrap();
+
+        let candidates = find_positions(&enc, StandardWiring::UKW_B, rotors, &stecker, 5).unwrap();
+        assert_eq!(candidates.len(), 5);
+        assert_eq!(candidates[0].0, "QEV");
+        assert!((candidates[0].1 - index_of_coincidence(PLAIN)).abs() < 1e-9);
+        assert!(candidates.windows(2).all(|w| w[0].1 >= w[1].1));
+
+        assert!(
+            find_positions(&enc, StandardWiring::UKW_B, rotors, &stecker, 0)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            find_positions("", StandardWiring::UKW_B, rotors, &stecker, 100_000)
+                .unwrap()
+                .len(),
+            26 * 26 * 26
+        );
+    }
+}
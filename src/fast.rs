@@ -0,0 +1,167 @@
+use alloc::vec::Vec;
+
+use crate::{rotor::Rotor, Enigma, EnigmaChar, EnigmaError, EnigmaResult};
+
+/// Handle for encoding characters in hot loops, created by `Enigma::prepare_fast()`.
+///
+/// The handle borrows the machine mutably for its whole lifetime and steps the machine's own rotors, so the machine
+/// is left in the same state as if every character had been passed to `Enigma::get_for_char()`. It has to be dropped
+/// before the machine can be used normally again.
+///
+/// The signal path through all rotors but the right one, the reflector and back only changes when one of those rotors
+/// moves, so it is cached as a single lookup table and recomputed only when needed.
+pub struct FastEncoder<'a> {
+    /// Machine being driven
+    enigma: &'a mut Enigma,
+    /// Combined substitution of all rotors but the right one, the reflector and the way back, for signals leaving the right rotor
+    inner: [u8; 26],
+    /// Positions of all rotors but the right one `inner` was computed for, left to right
+    inner_position: Vec<u8>,
+}
+
+impl<'a> FastEncoder<'a> {
+    /// Creates a new handle for the machine
+    ///
+    /// # Arguments
+    /// * `enigma` - Machine to drive
+    pub(crate) fn new(enigma: &'a mut Enigma) -> Self {
+        let mut z = Self {
+            enigma,
+            inner: [0u8; 26],
+            inner_position: Vec::new(),
+        };
+
+        z.update_inner();
+        z
+    }
+
+    /// Returns the slow rotors, i.e. all rotors but the right one, left to right
+    fn slow_rotors(&self) -> &[Rotor] {
+        let rotors = &self.enigma.rotors;
+        &rotors[..rotors.len() - 1]
+    }
+
+    /// Returns true if any of the slow rotors moved since `inner` was computed
+    fn inner_outdated(&self) -> bool {
+        !self
+            .slow_rotors()
+            .iter()
+            .map(|r| r.get_position().internal)
+            .eq(self.inner_position.iter().copied())
+    }
+
+    /// Recomputes the cached inner substitution for the current rotor positions
+    fn update_inner(&mut self) {
+        for (i, v) in self.inner.iter_mut().enumerate() {
+            let mut c = EnigmaChar {
+                internal: i as u8,
+                uppercase: true,
+            };
+
+            // Rotor lookups cannot fail for characters inside the alphabet
+            let slow = &self.enigma.rotors[..self.enigma.rotors.len() - 1];
+            for rotor in slow.iter().rev() {
+                rotor.get_for(&mut c, false).unwrap();
+            }
+            self.enigma.ukw.get_for(&mut c, false).unwrap();
+            for rotor in slow.iter() {
+                rotor.get_for(&mut c, true).unwrap();
+            }
+
+            *v = c.internal;
+        }
+
+        self.inner_position = self
+            .slow_rotors()
+            .iter()
+            .map(|r| r.get_position().internal)
+            .collect();
+    }
+
+    /// Runs a single character through the machine, with the same result as `Enigma::get_for_char()`
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - Character to encode
+    pub fn encode_char(&mut self, c: char) -> EnigmaResult<char> {
+        self.encode(c).map(char::from)
+    }
+
+    /// Runs a single character through the machine, keeping the case of the input
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - Character to encode
+    pub(crate) fn encode(&mut self, c: char) -> EnigmaResult<EnigmaChar> {
+        let mut c = match EnigmaChar::try_from(c) {
+            Ok(c) => c,
+            Err(EnigmaError::InvalidChar(c)) => return Err(EnigmaError::UnsupportedCharacter(c)),
+            Err(e) => return Err(e),
+        };
+
+        self.enigma.turn_rotors();
+        if self.inner_outdated() {
+            self.update_inner();
+        }
+
+        self.enigma.plugboard(&mut c, false);
+        let fast = self.enigma.rotors.last().unwrap();
+        fast.get_for(&mut c, false)?;
+        c.internal = self.inner[c.internal as usize];
+        fast.get_for(&mut c, true)?;
+        self.enigma.plugboard(&mut c, true);
+
+        Ok(c)
+    }
+}
+
+/// The cached table is derived from the rotor wirings and positions, so it is cleared like them
+#[cfg(feature = "zeroize")]
+impl Drop for FastEncoder<'_> {
+    fn drop(&mut self) {
+        crate::wipe(&mut self.inner);
+        crate::wipe(&mut self.inner_position);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{steckerbrett, wiring::StandardWiring, Enigma};
+
+    #[test]
+    fn test_fast_encoder() {
+        let mut slow = Enigma::standard(
+            StandardWiring::UKW_B,
+            StandardWiring::I,
+            StandardWiring::II,
+            StandardWiring::III,
+            steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+        )
+        .unwrap();
+        let mut fast = Enigma::standard(
+            StandardWiring::UKW_B,
+            StandardWiring::I,
+            StandardWiring::II,
+            StandardWiring::III,
+            steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+        )
+        .unwrap();
+
+        slow.set_position_str("ADS").unwrap();
+        fast.set_position_str("ADS").unwrap();
+
+        let input = "Bida Leonardovi".repeat(50);
+        let expected = slow.get_for_str(&input, true, true).unwrap();
+
+        let enc: String = {
+            let mut encoder = fast.prepare_fast();
+            input
+                .chars()
+                .map(|c| encoder.encode_char(c).unwrap_or(c))
+                .collect()
+        };
+
+        assert_eq!(expected, enc);
+        assert_eq!(slow.get_position_str(), fast.get_position_str());
+    }
+}
@@ -0,0 +1,193 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{Enigma, EnigmaConfig, EnigmaError, EnigmaResult};
+
+/// Struct representing a monthly key sheet, holding the daily settings of the machine
+///
+/// # Examples
+///
+/// ```
+/// use enigma::KeySheet;
+///
+/// let sheet = KeySheet::parse(
+///     "31 B II IV V BUL AAA AV BS CG DL FU HZ IN KM OW RX
+///      30 C I VIII III FNZ AAA BQ CR DI EJ KW MT OS PX UZ GH",
+/// )
+/// .unwrap();
+///
+/// let mut enigma = sheet.for_day(31).unwrap();
+/// enigma.set_position_str("BLA").unwrap();
+///
+/// assert!(sheet.for_day(1).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySheet {
+    /// Settings of day `i + 1` of the month at index `i`
+    days: Vec<Option<EnigmaConfig>>,
+}
+
+impl KeySheet {
+    /// Number of days a sheet can hold
+    pub const DAYS: u8 = 31;
+
+    /// Creates a sheet without any settings
+    pub fn new() -> Self {
+        Self {
+            days: vec![None; Self::DAYS as usize],
+        }
+    }
+
+    /// Creates a sheet from text with one day per line.
+    ///
+    /// Every non-empty line holds the settings of one day in the format of `Enigma::from_settings()`, optionally
+    /// preceded by the day of the month. A line without a day is for the day after the previous line, starting with
+    /// day 1. Sheets written like the printed ones, from day 31 down, have to give the day on every line.
+    ///
+    /// # Errors
+    /// * `EnigmaError::InvalidDay` - A day is outside of 1 to 31 or listed twice
+    /// * Any error of `Enigma::from_settings()` for a line with invalid settings
+    ///
+    /// # Arguments
+    /// * `text` - Settings to parse
+    pub fn parse(text: &str) -> EnigmaResult<Self> {
+        let mut sheet = Self::new();
+        let mut day = 0u8;
+
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let settings = match line.split_once(char::is_whitespace) {
+                Some((first, rest)) if first.bytes().all(|b| b.is_ascii_digit()) => {
+                    day = first
+                        .parse()
+                        .map_err(|_| EnigmaError::InvalidDay(u8::MAX))?;
+                    rest
+                }
+                _ => {
+                    day = day.saturating_add(1);
+                    line
+                }
+            };
+
+            if sheet.get(day).is_some() {
+                return Err(EnigmaError::InvalidDay(day));
+            }
+            sheet.set(day, Enigma::from_settings(settings)?.config())?;
+        }
+
+        Ok(sheet)
+    }
+
+    /// Creates a sheet with random settings for days 1 to `days`, each from `Enigma::random()`
+    ///
+    /// Returns `EnigmaError::InvalidDay` if `days` is more than 31.
+    ///
+    /// # Arguments
+    /// * `pick` - Source of random indices, as for `Enigma::random()`
+    /// * `days` - Number of days to fill
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::KeySheet;
+    ///
+    /// let mut state = 7u64;
+    /// let sheet = KeySheet::generate(
+    ///     |n| {
+    ///         state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    ///         (state >> 33) as usize % n
+    ///     },
+    ///     30,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(sheet.days().len(), 30);
+    /// assert_eq!(KeySheet::parse(&sheet.to_string()).unwrap(), sheet);
+    /// ```
+    pub fn generate(mut pick: impl FnMut(usize) -> usize, days: u8) -> EnigmaResult<Self> {
+        if days > Self::DAYS {
+            return Err(EnigmaError::InvalidDay(days));
+        }
+
+        let mut sheet = Self::new();
+        for day in 1..=days {
+            sheet.set(day, Enigma::random(&mut pick).config())?;
+        }
+
+        Ok(sheet)
+    }
+
+    /// Returns the settings of a day, or `None` if the sheet has no entry for it
+    ///
+    /// # Arguments
+    /// * `day` - Day of the month, 1 to 31
+    pub fn get(&self, day: u8) -> Option<&EnigmaConfig> {
+        self.days.get(usize::from(day).checked_sub(1)?)?.as_ref()
+    }
+
+    /// Sets the settings of a day, replacing the previous entry
+    ///
+    /// Only settings that can be written in the format of `Enigma::from_settings()` fit on a sheet. They are stored as
+    /// `Enigma::config()` returns them for the machine they build, e.g. with the plugboard pairs in alphabetical order.
+    ///
+    /// # Errors
+    /// * `EnigmaError::InvalidDay` - The day is outside of 1 to 31
+    /// * `EnigmaError::InvalidSettings` - The settings use custom wirings or a rotor bank `from_settings()` doesn't build
+    /// * Any error of `Enigma::from_config()` for settings that don't build a machine
+    ///
+    /// # Arguments
+    /// * `day` - Day of the month, 1 to 31
+    /// * `config` - Settings of the machine for that day
+    pub fn set(&mut self, day: u8, config: EnigmaConfig) -> EnigmaResult<()> {
+        let entry = usize::from(day)
+            .checked_sub(1)
+            .and_then(|i| self.days.get_mut(i))
+            .ok_or(EnigmaError::InvalidDay(day))?;
+
+        let enigma = Enigma::from_config(&config)?;
+        let (config, settings) = (enigma.config(), enigma.to_settings());
+        match Enigma::from_settings(&settings) {
+            Ok(e) if e.config() == config => *entry = Some(config),
+            _ => return Err(EnigmaError::InvalidSettings(settings)),
+        }
+
+        Ok(())
+    }
+
+    /// Builds a machine with the settings of a day
+    ///
+    /// Returns `EnigmaError::InvalidDay` if the sheet has no entry for the day.
+    ///
+    /// # Arguments
+    /// * `day` - Day of the month, 1 to 31
+    pub fn for_day(&self, day: u8) -> EnigmaResult<Enigma> {
+        Enigma::from_config(self.get(day).ok_or(EnigmaError::InvalidDay(day))?)
+    }
+
+    /// Returns the days the sheet has settings for, in ascending order
+    pub fn days(&self) -> Vec<u8> {
+        (1..=Self::DAYS)
+            .filter(|d| self.get(*d).is_some())
+            .collect()
+    }
+}
+
+impl Default for KeySheet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes one line per day in the format of `KeySheet::parse()`, from day 31 down like the printed sheets, each
+/// starting with the day of the month.
+impl core::fmt::Display for KeySheet {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for day in (1..=Self::DAYS).rev() {
+            if let Some(config) = self.get(day) {
+                // `set()` only stores settings that build a machine
+                let enigma = Enigma::from_config(config).expect("key sheet entry builds a machine");
+                writeln!(f, "{} {}", day, enigma.to_settings())?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,9 +1,33 @@
-use std::collections::HashMap;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+extern crate alloc;
+
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+pub use builder::EnigmaBuilder;
+pub use config::{EnigmaConfig, RotorConfig, UhrConfig};
+pub use fast::FastEncoder;
+pub use keysheet::KeySheet;
 use rotor::Rotor;
+pub use uhr::Uhr;
 use wiring::{StandardWiring, Wiring};
 
+#[cfg(feature = "std")]
+pub mod analysis;
+mod builder;
+mod config;
+pub mod crack;
+mod fast;
+mod keysheet;
+pub mod preprocess;
 mod rotor;
+mod uhr;
 pub mod wiring;
 
 /// Result returned by this crate's functions
@@ -15,22 +39,66 @@ pub enum EnigmaError {
     InvalidChar(char),
     InvalidNumber(u8),
     InvalidPosition(String),
+    InvalidPositionLength(String, usize),
     InvalidSteckerbrettString(String),
     UnsupportedCharacter(char),
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    InvalidRewind(usize),
+    InvalidWiringName(String),
+    InvalidRotorSelection(String),
+    InvalidReflector(String),
+    InvalidUhrDial(u8),
+    MissingUhr,
+    InvalidSettings(String),
+    InvalidDay(u8),
 }
 
-impl std::fmt::Display for EnigmaError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for EnigmaError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::InvalidChar(c) => write!(f, "Cannot index using invalid character: {}", c),
             Self::InvalidNumber(c) => write!(f, "Cannot index using invalid number: {}", c),
             Self::InvalidPosition(s) => {
                 write!(f, "String '{}' cannot be used to set position", s)
             }
+            Self::InvalidPositionLength(s, n) => {
+                write!(
+                    f,
+                    "Position '{}' must have exactly {} characters, one per rotor",
+                    s, n
+                )
+            }
             Self::UnsupportedCharacter(c) => write!(f, "Character '{}' cannot be encoded", c),
             Self::InvalidSteckerbrettString(s) => {
                 write!(f, "String '{}' is not representing valid stecker pairs!", s)
             }
+            #[cfg(feature = "std")]
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::InvalidRewind(n) => write!(
+                f,
+                "Cannot rewind {} keypresses past the last set starting position",
+                n
+            ),
+            Self::InvalidWiringName(s) => write!(f, "'{}' is not a known wiring name", s),
+            Self::InvalidRotorSelection(s) => {
+                write!(f, "'{}' is not a valid selection of rotors", s)
+            }
+            Self::InvalidReflector(s) => write!(
+                f,
+                "Wiring '{}' is not a valid reflector, it has to pair every letter with a different one",
+                s
+            ),
+            Self::InvalidUhrDial(n) => {
+                write!(f, "Uhr cannot be set to {}, it has positions 00 to 39", n)
+            }
+            Self::MissingUhr => write!(f, "No Uhr is installed"),
+            Self::InvalidSettings(s) => write!(
+                f,
+                "Settings '{}' must list the reflector, rotors, rings and positions",
+                s
+            ),
+            Self::InvalidDay(d) => write!(f, "Key sheet has no entry for day {}", d),
         }
     }
 }
@@ -46,6 +114,11 @@ pub struct EnigmaChar {
 impl TryFrom<char> for EnigmaChar {
     type Error = EnigmaError;
     fn try_from(value: char) -> Result<Self, Self::Error> {
+        // Checked before the cast, which would keep only the low byte of other characters
+        if !value.is_ascii() {
+            return Err(EnigmaError::InvalidChar(value));
+        }
+
         let uppercase = match value as u8 {
             65..=90 => true,
             97..=122 => false,
@@ -59,6 +132,23 @@ impl TryFrom<char> for EnigmaChar {
     }
 }
 
+impl EnigmaChar {
+    /// Returns the uppercase letter at the position in the alphabet as used on key sheets, 1 being 'A'. Returns
+    /// `EnigmaError::InvalidNumber` outside of 1..=26.
+    ///
+    /// # Arguments
+    /// * `n` - Position in the alphabet, starting at 1
+    fn from_numeric(n: u8) -> EnigmaResult<Self> {
+        match n {
+            1..=26 => Ok(Self {
+                internal: n - 1,
+                uppercase: true,
+            }),
+            _ => Err(EnigmaError::InvalidNumber(n)),
+        }
+    }
+}
+
 impl TryFrom<&char> for EnigmaChar {
     type Error = EnigmaError;
     fn try_from(value: &char) -> Result<Self, Self::Error> {
@@ -92,8 +182,14 @@ impl PartialEq for EnigmaChar {
 
 /// Struct representing a plugboard
 ///
+/// Index `i` holds the alphabet position the `i`-th letter is connected to, or `i` itself if the letter is not plugged.
+///
 /// Use the steckerbrett! macro for construction unless advanced behaviour is needed.
-pub struct Steckerbrett(pub HashMap<u8, u8>);
+///
+/// Plugboards compare and hash by the substitution they perform, so the order the cables were plugged in doesn't
+/// matter.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Steckerbrett(pub [u8; 26]);
 
 /// Macro for creating plugboards
 ///
@@ -114,7 +210,7 @@ pub struct Steckerbrett(pub HashMap<u8, u8>);
 #[macro_export]
 macro_rules! steckerbrett {
     () => {
-        $crate::Steckerbrett(std::collections::HashMap::new())
+        $crate::Steckerbrett::new()
     };
 
     ($a:expr) => {
@@ -127,32 +223,276 @@ macro_rules! steckerbrett {
 }
 
 impl Steckerbrett {
-    /// Run a character through this plugboard
+    /// Number of cables supplied with a real plugboard
+    pub const MAX_PAIRS: usize = 10;
+
+    /// Creates an empty plugboard
+    pub fn new() -> Self {
+        let mut z = [0u8; 26];
+        for (i, v) in z.iter_mut().enumerate() {
+            *v = i as u8;
+        }
+
+        Self(z)
+    }
+
+    /// Run a character through this plugboard. Characters outside of the alphabet are left unchanged.
     ///
     /// # Arguments
     /// * `char` - Input character
     pub fn get(&self, char: &mut EnigmaChar) {
-        if let Some(val) = self.0.get(&char.internal) {
-            char.internal = *val;
+        if let Some(&v) = self.0.get(char.internal as usize) {
+            char.internal = v;
         }
     }
-}
 
-impl TryFrom<&[(char, char)]> for Steckerbrett {
-    type Error = EnigmaError;
-    fn try_from(value: &[(char, char)]) -> Result<Self, Self::Error> {
+    /// Returns true if the letter is connected to a cable. Characters outside of the alphabet are never connected.
+    ///
+    /// # Arguments
+    /// * `c` - Letter to check, case insensitive
+    pub fn contains(&self, c: char) -> bool {
+        EnigmaChar::try_from(c).is_ok_and(|c| self.0[c.internal as usize] != c.internal)
+    }
+
+    /// Returns the number of cables plugged in
+    pub fn len(&self) -> usize {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(a, b)| *a as u8 != **b)
+            .count()
+            / 2
+    }
+
+    /// Returns true if no cables are plugged in
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the cables of this plugboard as uppercase letter pairs, sorted alphabetically.
+    ///
+    /// Each cable is listed once, with the alphabetically smaller letter first.
+    pub fn pairs(&self) -> Vec<(char, char)> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(a, b)| (*a as u8) < **b)
+            .map(|(a, b)| {
+                (
+                    char::from(EnigmaChar {
+                        internal: a as u8,
+                        uppercase: true,
+                    }),
+                    char::from(EnigmaChar {
+                        internal: *b,
+                        uppercase: true,
+                    }),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the substitution this plugboard performs as a 26-letter uppercase string.
+    ///
+    /// The `i`-th character is the letter the `i`-th letter of the alphabet is connected to, or the letter itself if
+    /// it is not plugged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::steckerbrett;
+    ///
+    /// let s = steckerbrett!('A' => 'E').unwrap();
+    ///
+    /// assert_eq!(s.to_alphabet(), "EBCDAFGHIJKLMNOPQRSTUVWXYZ");
+    /// ```
+    pub fn to_alphabet(&self) -> String {
+        self.0
+            .iter()
+            .map(|i| {
+                char::from(EnigmaChar {
+                    internal: *i,
+                    uppercase: true,
+                })
+            })
+            .collect()
+    }
+
+    /// Replaces all cables of this plugboard with the provided pairs.
+    ///
+    /// The new set is validated as a whole before it is applied: no letter may be connected to itself or appear in
+    /// more than one pair, and there may be at most `MAX_PAIRS` pairs. On error the plugboard is left untouched.
+    ///
+    /// # Arguments
+    /// * `pairs` - New cables
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::steckerbrett;
+    ///
+    /// let mut s = steckerbrett!("AE IO").unwrap();
+    ///
+    /// assert!(s.replace_pairs(&[('F', 'R'), ('X', 'F')]).is_err());
+    /// assert_eq!(s.to_string(), "AE IO");
+    ///
+    /// s.replace_pairs(&[('F', 'R'), ('X', 'Q')]).unwrap();
+    /// assert_eq!(s.to_string(), "FR QX");
+    /// ```
+    pub fn replace_pairs(&mut self, pairs: &[(char, char)]) -> EnigmaResult<()> {
+        *self = Self::validated(pairs)?;
+
+        Ok(())
+    }
+
+    /// Builds a plugboard from the provided pairs, rejecting self-connections, letters used in more than one pair and
+    /// more than `MAX_PAIRS` pairs
+    ///
+    /// # Arguments
+    /// * `pairs` - Cables
+    fn validated(pairs: &[(char, char)]) -> EnigmaResult<Self> {
+        let invalid = || {
+            EnigmaError::InvalidSteckerbrettString(
+                pairs
+                    .iter()
+                    .map(|(a, b)| format!("{}{}", a, b))
+                    .collect::<Vec<String>>()
+                    .join(" "),
+            )
+        };
+
+        if pairs.len() > Self::MAX_PAIRS {
+            return Err(invalid());
+        }
+
         let mut z = steckerbrett!();
 
-        for (c, d) in value.iter() {
-            let c = EnigmaChar::try_from(c)?;
-            let d = EnigmaChar::try_from(d)?;
+        for (c, d) in pairs.iter() {
+            let c = EnigmaChar::try_from(c)?.internal as usize;
+            let d = EnigmaChar::try_from(d)?.internal as usize;
+
+            if c == d || z.0[c] != c as u8 || z.0[d] != d as u8 {
+                return Err(invalid());
+            }
 
-            z.0.insert(c.internal, d.internal);
-            z.0.insert(d.internal, c.internal);
+            z.0[c] = d as u8;
+            z.0[d] = c as u8;
         }
 
         Ok(z)
     }
+
+    /// Returns the cables that break the provided rules, in the same format as `pairs()`
+    ///
+    /// # Arguments
+    /// * `rules` - Rules to check against
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{steckerbrett, PlugRules};
+    ///
+    /// let s = steckerbrett!("AB FR QW").unwrap();
+    ///
+    /// assert_eq!(s.violates_rules(PlugRules::NO_CONSECUTIVE), vec![('A', 'B')]);
+    /// assert_eq!(s.violates_rules(PlugRules::STRICT), vec![('A', 'B'), ('Q', 'W')]);
+    /// assert!(s.violates_rules(PlugRules::NONE).is_empty());
+    /// ```
+    pub fn violates_rules(&self, rules: PlugRules) -> Vec<(char, char)> {
+        self.pairs()
+            .into_iter()
+            .filter(|(a, b)| rules.forbids(*a, *b))
+            .collect()
+    }
+}
+
+impl Default for Steckerbrett {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Restrictions on plugboard cabling, checked by `Steckerbrett::violates_rules()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PlugRules {
+    /// Forbid cables between letters that follow each other in the alphabet, e.g. A-B or M-N
+    pub no_consecutive_letters: bool,
+    /// Also treat Z and A as consecutive. Only used together with `no_consecutive_letters`
+    pub wrap_around: bool,
+    /// Forbid cables between letters that sit next to each other in a row of the Enigma keyboard, e.g. Q-W or N-M
+    pub no_keyboard_neighbours: bool,
+}
+
+impl PlugRules {
+    /// No restrictions
+    pub const NONE: PlugRules = PlugRules {
+        no_consecutive_letters: false,
+        wrap_around: false,
+        no_keyboard_neighbours: false,
+    };
+
+    /// The German rule of never connecting letters adjacent in the alphabet, exploited at Bletchley Park as the
+    /// "consecutive stecker knock-out"
+    pub const NO_CONSECUTIVE: PlugRules = PlugRules {
+        no_consecutive_letters: true,
+        wrap_around: true,
+        no_keyboard_neighbours: false,
+    };
+
+    /// `NO_CONSECUTIVE` extended with keyboard neighbours. This is a teaching ruleset, not a historical regulation.
+    pub const STRICT: PlugRules = PlugRules {
+        no_consecutive_letters: true,
+        wrap_around: true,
+        no_keyboard_neighbours: true,
+    };
+
+    /// Rows of the Enigma keyboard
+    const KEYBOARD: [&'static str; 3] = ["QWERTZUIO", "ASDFGHJK", "PYXCVBNML"];
+
+    /// Returns true if a cable between the two uppercase letters breaks these rules
+    ///
+    /// # Arguments
+    /// * `a` - First letter
+    /// * `b` - Second letter
+    fn forbids(&self, a: char, b: char) -> bool {
+        let distance = (a as u8).abs_diff(b as u8);
+
+        if self.no_consecutive_letters && (distance == 1 || (self.wrap_around && distance == 25)) {
+            return true;
+        }
+
+        self.no_keyboard_neighbours
+            && Self::KEYBOARD.iter().any(|row| {
+                let row = row.as_bytes();
+                row.windows(2).any(|w| {
+                    (w[0] == a as u8 && w[1] == b as u8) || (w[0] == b as u8 && w[1] == a as u8)
+                })
+            })
+    }
+}
+
+/// Formats the plugboard as space separated pairs (e.g. "AE IO ML"), the same format accepted by `Steckerbrett::try_from(&str)`.
+impl core::fmt::Display for Steckerbrett {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (i, (a, b)) in self.pairs().iter().enumerate() {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}{}", a, b)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<&[(char, char)]> for Steckerbrett {
+    type Error = EnigmaError;
+
+    /// Builds a plugboard from the provided pairs. Returns `EnigmaError::InvalidSteckerbrettString` if a letter is
+    /// connected to itself or appears in more than one pair, or if there are more than `Steckerbrett::MAX_PAIRS` pairs.
+    fn try_from(value: &[(char, char)]) -> Result<Self, Self::Error> {
+        Self::validated(value)
+    }
 }
 
 impl TryFrom<&Vec<(char, char)>> for Steckerbrett {
@@ -182,6 +522,76 @@ impl TryFrom<&str> for Steckerbrett {
     }
 }
 
+/// Handling of line breaks while encoding strings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NewlinePolicy {
+    /// Line breaks are treated like any other unsupported character
+    #[default]
+    Unsupported,
+    /// Line breaks ('\n' and '\r') are always kept in the output
+    Preserve,
+    /// Every '\n' is replaced by the provided character and '\r' is dropped
+    Replace(char),
+}
+
+/// Handling of characters outside of the alphabet while encoding strings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnsupportedPolicy {
+    /// Unsupported characters are left out of the output
+    #[default]
+    Drop,
+    /// Unsupported characters are copied to the output unchanged
+    Preserve,
+    /// Encoding stops with `EnigmaError::UnsupportedCharacter`
+    Error,
+}
+
+/// Options controlling how strings are encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EncodeOptions {
+    /// How non-alphabet characters are handled
+    pub unsupported: UnsupportedPolicy,
+    /// Whether output characters should match the case of the input characters
+    pub preserve_case: bool,
+    /// How line breaks are handled, independently of `preserve_unsupported`
+    pub newlines: NewlinePolicy,
+}
+
+impl EncodeOptions {
+    /// Creates new encoding options
+    ///
+    /// # Arguments
+    ///
+    /// * `preserve_unsupported` - Whether non-alphabet characters should be preserved in the output. If false, they are dropped.
+    /// * `preserve_case` - Whether output characters should match the case of the input characters
+    pub fn new(preserve_unsupported: bool, preserve_case: bool) -> Self {
+        Self {
+            unsupported: if preserve_unsupported {
+                UnsupportedPolicy::Preserve
+            } else {
+                UnsupportedPolicy::Drop
+            },
+            preserve_case,
+            newlines: NewlinePolicy::Unsupported,
+        }
+    }
+}
+
+/// Struct describing which rotors will advance on the next keypress.
+///
+/// Returned by `Enigma::next_step_info()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NextStep {
+    /// Whether the left rotor (rotor 1) will advance
+    pub left: bool,
+    /// Whether the middle rotor (rotor 2) will advance
+    pub middle: bool,
+    /// Whether the right rotor (rotor 3) will advance. Always true, as the right rotor advances on every keypress.
+    pub right: bool,
+    /// Whether the middle rotor will advance because of its own notch, taking the left rotor with it (double-step)
+    pub double_step: bool,
+}
+
 /// Struct representing a fully defined M3 Enigma machine.
 ///
 /// # Examples
@@ -196,30 +606,134 @@ impl TryFrom<&str> for Steckerbrett {
 ///     StandardWiring::II,
 ///     StandardWiring::III,
 ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
-/// );
+/// ).unwrap();
 ///
 /// /// Encode "test"
 /// let enc = enigma.get_for_str("test", false, true).unwrap();
 ///
 /// assert_eq!("olkr", enc);
 /// ```
+#[derive(Clone)]
 pub struct Enigma {
     /// Reflector rotor
     ukw: Rotor,
-    /// Left rotor (rotor 1)
-    rotor_l: Rotor,
-    /// Middle rotor (rotor 2)
-    rotor_m: Rotor,
-    /// Right rotor (rotor 3)
-    rotor_r: Rotor,
+    /// Rotors, left to right. The last one is the fastest.
+    rotors: Vec<Rotor>,
+    /// Number of leftmost rotors that are never stepped, 1 for the Greek rotor of the M4
+    fixed: usize,
     /// Plugboard
     steckerbrett: Steckerbrett,
+    /// Uhr used in place of the plugboard cables, if any
+    uhr: Option<Uhr>,
+    /// Rotor positions set by the most recent `set_position()` call, left to right
+    start: Vec<u8>,
+    /// Number of times the rotors were stepped since they were at `start`
+    steps: usize,
+}
+
+/// Overwrites the bytes with zeroes in a way the compiler can't optimize away, even if the memory is freed right after
+///
+/// # Arguments
+/// * `bytes` - Memory to clear
+#[cfg(feature = "zeroize")]
+pub(crate) fn wipe(bytes: &mut [u8]) {
+    for b in bytes.iter_mut() {
+        // SAFETY: `b` is a valid, exclusive reference
+        unsafe { core::ptr::write_volatile(b, 0) };
+    }
+
+    core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Steckerbrett {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+/// The rotors and the plugboard clear themselves, the machine only clears its record of the start position
+#[cfg(feature = "zeroize")]
+impl Drop for Enigma {
+    fn drop(&mut self) {
+        wipe(&mut self.start);
+        self.steps = 0;
+    }
+}
+
+/// Returns the name of a standard wiring, or its template if it is a custom one
+///
+/// # Arguments
+/// * `wiring` - Wiring to name
+fn wiring_name(wiring: &Wiring) -> String {
+    match StandardWiring::identify(wiring) {
+        Some(w) => w.to_string(),
+        None => wiring.to_string(),
+    }
+}
+
+/// Formats the machine as a one-line summary, e.g. "Enigma[UKW_B | I II III | pos=AAT | ring=AAA | stecker=3]".
+///
+/// Standard wirings are shown by name and custom ones as their template. The plugboard is only shown as the number of
+/// cables, and an installed Uhr as its dial position, e.g. "uhr=27".
+impl core::fmt::Display for Enigma {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Enigma[{} |", wiring_name(self.ukw.wiring()))?;
+        for rotor in &self.rotors {
+            write!(f, " {}", wiring_name(rotor.wiring()))?;
+        }
+        write!(
+            f,
+            " | pos={} | ring={} | stecker={}",
+            self.get_position_str(),
+            self.get_ring_str(),
+            self.steckerbrett.pairs().len()
+        )?;
+        if let Some(uhr) = &self.uhr {
+            write!(f, " | uhr={:02}", uhr.dial())?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Lists the complete configuration, including the plugboard cables
+impl core::fmt::Debug for Enigma {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let rotors: Vec<String> = self
+            .rotors
+            .iter()
+            .map(|r| wiring_name(r.wiring()))
+            .collect();
+
+        f.debug_struct("Enigma")
+            .field("reflector", &wiring_name(self.ukw.wiring()))
+            .field("rotors", &rotors)
+            .field("positions", &self.get_position_str())
+            .field("rings", &self.get_ring_str())
+            .field("plugboard", &self.steckerbrett.to_string())
+            .field("uhr", &self.uhr)
+            .finish()
+    }
+}
+
+/// Two machines are equal if they have the same reflector, rotors, rotor positions, ring settings and plugboard, i.e.
+/// if they will encode any text the same way. How the rotors got to their positions is not compared.
+impl PartialEq for Enigma {
+    fn eq(&self, other: &Self) -> bool {
+        self.ukw == other.ukw
+            && self.rotors == other.rotors
+            && self.fixed == other.fixed
+            && self.steckerbrett == other.steckerbrett
+            && self.uhr == other.uhr
+    }
 }
 
 impl Enigma {
     /// Creates a new enigma machine with the specified custom wirings.
     ///
-    /// If you don't need to specify a custom wiring, using Enigma::standard() is preferred.
+    /// If you don't need to specify a custom wiring, using Enigma::standard() is preferred. Use `Enigma::with_rotors()`
+    /// for a different number of rotors.
     ///
     /// # Arguments
     ///
@@ -237,15 +751,86 @@ impl Enigma {
     ) -> Self {
         Self {
             ukw: Rotor::new(ukw),
-            rotor_l: Rotor::new(wiring_l),
-            rotor_m: Rotor::new(wiring_m),
-            rotor_r: Rotor::new(wiring_r),
+            rotors: vec![
+                Rotor::new(wiring_l),
+                Rotor::new(wiring_m),
+                Rotor::new(wiring_r),
+            ],
+            fixed: 0,
             steckerbrett: stecker,
+            uhr: None,
+            start: vec![0; 3],
+            steps: 0,
+        }
+    }
+
+    /// Creates a new enigma machine with any number of rotors, at least three.
+    ///
+    /// All rotors step the way the three rotors of an M3 do: the right rotor on every keypress, and every other rotor
+    /// when its right neighbour is at a notch. Methods working with exactly three rotors, such as `set_position()`
+    /// and `get_position()`, refer to the three rightmost ones. Returns `EnigmaError::InvalidRotorSelection` for
+    /// fewer than three rotors.
+    ///
+    /// # Arguments
+    ///
+    /// * `ukw` - Wiring of the reflector
+    /// * `rotors` - Wirings of the rotors, left to right. The last one is the fastest.
+    /// * `stecker` - Plugboard
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::with_rotors(
+    ///     StandardWiring::UKW_B.into(),
+    ///     vec![
+    ///         StandardWiring::V.into(),
+    ///         StandardWiring::I.into(),
+    ///         StandardWiring::II.into(),
+    ///         StandardWiring::III.into(),
+    ///         StandardWiring::IV.into(),
+    ///     ],
+    ///     steckerbrett!(),
+    /// )
+    /// .unwrap();
+    ///
+    /// enigma.set_position_str("ABCDE").unwrap();
+    /// assert_eq!(5, enigma.rotor_count());
+    /// ```
+    pub fn with_rotors(
+        ukw: Wiring,
+        rotors: Vec<Wiring>,
+        stecker: Steckerbrett,
+    ) -> EnigmaResult<Self> {
+        if rotors.len() < 3 {
+            return Err(EnigmaError::InvalidRotorSelection(format!(
+                "{} rotors",
+                rotors.len()
+            )));
         }
+
+        Ok(Self {
+            ukw: Rotor::new(ukw),
+            start: vec![0; rotors.len()],
+            rotors: rotors.into_iter().map(Rotor::new).collect(),
+            fixed: 0,
+            steckerbrett: stecker,
+            uhr: None,
+            steps: 0,
+        })
+    }
+
+    /// Returns a builder for configuring an Enigma M3 machine step by step
+    pub fn builder() -> EnigmaBuilder {
+        EnigmaBuilder::new()
     }
 
     /// Creates a new Enigma M3 machine with the specified standard wirings
     ///
+    /// Returns `EnigmaError::InvalidRotorSelection` if `ukw` is not a reflector, or if the rotors are not three different
+    /// ones out of I to VIII, as a machine only came with one of each.
+    ///
     /// # Arguments
     ///
     /// * `ukw` - Wiring of the reflector
@@ -266,7 +851,7 @@ impl Enigma {
     ///     StandardWiring::II,
     ///     StandardWiring::III,
     ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
-    /// );
+    /// ).unwrap();
     /// ```
     pub fn standard(
         ukw: StandardWiring,
@@ -274,14 +859,160 @@ impl Enigma {
         wiring_m: StandardWiring,
         wiring_r: StandardWiring,
         stecker: Steckerbrett,
-    ) -> Self {
-        Self::new(
+    ) -> EnigmaResult<Self> {
+        check_selection(Some(ukw), &[wiring_l, wiring_m, wiring_r], 0, || {
+            format!("{} {} {} {}", ukw, wiring_l, wiring_m, wiring_r)
+        })?;
+
+        Ok(Self::new(
             ukw.into(),
             wiring_l.into(),
             wiring_m.into(),
             wiring_r.into(),
             stecker,
-        )
+        ))
+    }
+
+    /// Creates a new Enigma M4 machine with the specified standard wirings
+    ///
+    /// The M4 has a fourth, Greek rotor between the reflector and the left rotor. It can be set to any position, but
+    /// never steps. Position and ring strings of an M4 are four characters long, starting with the Greek rotor.
+    ///
+    /// Returns `EnigmaError::InvalidRotorSelection` if `ukw` is not a reflector, `greek` is not a Greek rotor, or the
+    /// other rotors are not three different ones out of I to VIII.
+    ///
+    /// # Arguments
+    ///
+    /// * `ukw` - Wiring of the reflector, usually `UKW_B_Thin` or `UKW_C_Thin`
+    /// * `greek` - Wiring of the Greek rotor (rotor 0), usually `Beta` or `Gamma`
+    /// * `wiring_l` - Wiring of the left rotor (rotor 1)
+    /// * `wiring_m` - Wiring of the middle rotor (rotor 2)
+    /// * `wiring_r` - Wiring of the right rotor (rotor 3)
+    /// * `stecker` - Plugboard
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::m4(
+    ///     StandardWiring::UKW_B_Thin,
+    ///     StandardWiring::Beta,
+    ///     StandardWiring::II,
+    ///     StandardWiring::IV,
+    ///     StandardWiring::I,
+    ///     steckerbrett!(),
+    /// ).unwrap();
+    ///
+    /// enigma.set_position_str("VJNA").unwrap();
+    /// assert_eq!(4, enigma.rotor_count());
+    /// assert_eq!("VJNA", enigma.get_position_str());
+    /// ```
+    pub fn m4(
+        ukw: StandardWiring,
+        greek: StandardWiring,
+        wiring_l: StandardWiring,
+        wiring_m: StandardWiring,
+        wiring_r: StandardWiring,
+        stecker: Steckerbrett,
+    ) -> EnigmaResult<Self> {
+        check_selection(Some(ukw), &[greek, wiring_l, wiring_m, wiring_r], 1, || {
+            format!("{} {} {} {} {}", ukw, greek, wiring_l, wiring_m, wiring_r)
+        })?;
+
+        let mut z = Self::with_rotors(
+            ukw.into(),
+            vec![
+                greek.into(),
+                wiring_l.into(),
+                wiring_m.into(),
+                wiring_r.into(),
+            ],
+            stecker,
+        )?;
+
+        z.fixed = 1;
+        Ok(z)
+    }
+
+    /// Creates an Enigma M3 machine with a random configuration, e.g. for generating daily keys.
+    ///
+    /// Three distinct rotors are picked from I–V and the reflector from UKW_A, UKW_B and UKW_C. Positions and ring
+    /// settings are random, and ten cables connect twenty distinct letters on the plugboard.
+    ///
+    /// The randomness comes from `pick`, which receives a count `n` and has to return an index in `0..n`. This keeps
+    /// the crate free of dependencies while allowing any generator, e.g. `|n| rng.gen_range(0..n)` with `rand`.
+    /// Indices out of range are wrapped around.
+    ///
+    /// # Arguments
+    ///
+    /// * `pick` - Source of random indices
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::Enigma;
+    ///
+    /// // A tiny linear congruential generator, use a proper one for real keys
+    /// let mut state = 42u64;
+    /// let enigma = Enigma::random(|n| {
+    ///     state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    ///     (state >> 33) as usize % n
+    /// });
+    ///
+    /// assert_eq!(enigma.config().plugboard.len(), 10);
+    /// ```
+    pub fn random(mut pick: impl FnMut(usize) -> usize) -> Self {
+        let mut pick = move |n: usize| pick(n) % n;
+
+        let ukw = [
+            StandardWiring::UKW_A,
+            StandardWiring::UKW_B,
+            StandardWiring::UKW_C,
+        ][pick(3)];
+
+        let mut rotors = StandardWiring::ALL[..5].to_vec();
+        let [l, m, r] = [(); 3].map(|_| rotors.remove(pick(rotors.len())));
+
+        let mut letters: Vec<char> = ('A'..='Z').collect();
+        let pairs: Vec<(char, char)> = (0..Steckerbrett::MAX_PAIRS)
+            .map(|_| {
+                let a = letters.remove(pick(letters.len()));
+                (a, letters.remove(pick(letters.len())))
+            })
+            .collect();
+
+        let rings: String = (0..3).map(|_| (b'A' + pick(26) as u8) as char).collect();
+        let positions: String = (0..3).map(|_| (b'A' + pick(26) as u8) as char).collect();
+
+        // Every part was picked within its valid range
+        let mut enigma =
+            Self::standard(ukw, l, m, r, Steckerbrett::validated(&pairs).unwrap()).unwrap();
+        enigma.set_ring_str(&rings).unwrap();
+        enigma.set_position_str(&positions).unwrap();
+
+        enigma
+    }
+
+    /// Returns the left, middle and right rotor, i.e. the three rightmost ones
+    fn lmr(&self) -> &[Rotor] {
+        &self.rotors[self.rotors.len() - 3..]
+    }
+
+    /// Returns the left, middle and right rotor, i.e. the three rightmost ones
+    fn lmr_mut(&mut self) -> &mut [Rotor] {
+        let n = self.rotors.len();
+        &mut self.rotors[n - 3..]
+    }
+
+    /// Remembers the current rotor positions as the start for `rewind()`
+    fn mark_start(&mut self) {
+        self.start = self
+            .rotors
+            .iter()
+            .map(|r| r.get_position().internal)
+            .collect();
+        self.steps = 0;
     }
 
     /// Sets the rotor's positions
@@ -297,197 +1028,2319 @@ impl Enigma {
         rotor_m: Option<char>,
         rotor_r: Option<char>,
     ) -> EnigmaResult<()> {
-        if let Some(c) = rotor_l {
-            self.rotor_l.set_position(&EnigmaChar::try_from(c)?)?;
-        }
+        let positions = [
+            rotor_l.map(EnigmaChar::try_from).transpose()?,
+            rotor_m.map(EnigmaChar::try_from).transpose()?,
+            rotor_r.map(EnigmaChar::try_from).transpose()?,
+        ];
 
-        if let Some(c) = rotor_m {
-            self.rotor_m.set_position(&EnigmaChar::try_from(c)?)?;
+        for (rotor, c) in self.lmr_mut().iter_mut().zip(positions) {
+            if let Some(c) = c {
+                rotor.set_position(&c)?;
+            }
         }
 
-        if let Some(c) = rotor_r {
-            self.rotor_r.set_position(&EnigmaChar::try_from(c)?)?;
-        }
+        self.mark_start();
 
         Ok(())
     }
 
-    /// Sets the rotor's positions specified by a string.
+    /// Sets the rotor's ring settings (Ringstellung). All rings are set to 'A' when a machine is created.
     ///
     /// # Arguments
     ///
-    /// * `position` - A three long string of ascii alphabet characters, each representing a rotor's position. Left to right.
-    ///
+    /// * `rotor_l` - Ring setting of the left rotor (rotor 1)
+    /// * `rotor_m` - Ring setting of the middle rotor (rotor 2)
+    /// * `rotor_r` - Ring setting of the right rotor (rotor 3)
+    pub fn set_ring(
+        &mut self,
+        rotor_l: Option<char>,
+        rotor_m: Option<char>,
+        rotor_r: Option<char>,
+    ) -> EnigmaResult<()> {
+        let rings = [
+            rotor_l.map(EnigmaChar::try_from).transpose()?,
+            rotor_m.map(EnigmaChar::try_from).transpose()?,
+            rotor_r.map(EnigmaChar::try_from).transpose()?,
+        ];
+
+        for (rotor, c) in self.lmr_mut().iter_mut().zip(rings) {
+            if let Some(c) = c {
+                rotor.set_ring(&c)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the ring settings of the left, middle and right rotor from numbers in the range 1..=26, as printed on key
+    /// sheets. 1 is the same as 'A', so `set_ring_numeric(2, 1, 1)` is the same as `set_ring_str("BAA")`. Returns
+    /// `EnigmaError::InvalidNumber` if a number is out of range, in which case no ring is changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `rotor_l` - Ring setting of the left rotor (rotor 1)
+    /// * `rotor_m` - Ring setting of the middle rotor (rotor 2)
+    /// * `rotor_r` - Ring setting of the right rotor (rotor 3)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!(),
+    /// ).unwrap();
+    ///
+    /// enigma.set_ring_numeric(2, 21, 12).unwrap();
+    /// assert_eq!("BUL", enigma.get_ring_str());
+    /// assert_eq!([2, 21, 12], enigma.get_ring_numeric());
+    ///
+    /// assert!(enigma.set_ring_numeric(0, 1, 27).is_err());
+    /// ```
+    pub fn set_ring_numeric(&mut self, rotor_l: u8, rotor_m: u8, rotor_r: u8) -> EnigmaResult<()> {
+        let rings = [
+            EnigmaChar::from_numeric(rotor_l)?,
+            EnigmaChar::from_numeric(rotor_m)?,
+            EnigmaChar::from_numeric(rotor_r)?,
+        ];
+
+        for (rotor, c) in self.lmr_mut().iter_mut().zip(rings) {
+            rotor.set_ring(&c)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets the positions of the left, middle and right rotor from numbers in the range 1..=26, 1 being 'A', and makes
+    /// them the start for `rewind()`. Returns `EnigmaError::InvalidNumber` if a number is out of range, in which case no
+    /// rotor is moved.
+    ///
+    /// # Arguments
+    ///
+    /// * `rotor_l` - Position of the left rotor (rotor 1)
+    /// * `rotor_m` - Position of the middle rotor (rotor 2)
+    /// * `rotor_r` - Position of the right rotor (rotor 3)
+    pub fn set_position_numeric(
+        &mut self,
+        rotor_l: u8,
+        rotor_m: u8,
+        rotor_r: u8,
+    ) -> EnigmaResult<()> {
+        let positions = [
+            EnigmaChar::from_numeric(rotor_l)?,
+            EnigmaChar::from_numeric(rotor_m)?,
+            EnigmaChar::from_numeric(rotor_r)?,
+        ];
+
+        for (rotor, c) in self.lmr_mut().iter_mut().zip(positions) {
+            rotor.set_position(&c)?;
+        }
+
+        self.mark_start();
+
+        Ok(())
+    }
+
+    /// Sets the rotor's ring settings specified by a string.
+    ///
+    /// # Arguments
+    ///
+    /// * `ring` - A string of ascii alphabet characters, one per rotor, each representing a rotor's ring setting. Left to right.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// // Creates an enigma machine
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!(),
+    /// ).unwrap();
+    ///
+    /// // Sets the rings of the left, middle and right rotor to 'B', 'U' and 'L'
+    /// enigma.set_ring_str("BUL").unwrap();
+    ///
+    /// assert_eq!("BUL", enigma.get_ring_str());
+    /// ```
+    pub fn set_ring_str(&mut self, ring: &str) -> EnigmaResult<()> {
+        if ring.chars().count() != self.rotor_count() {
+            return Err(crate::EnigmaError::InvalidPositionLength(
+                ring.to_owned(),
+                self.rotor_count(),
+            ));
+        }
+
+        let rings = ring
+            .chars()
+            .map(EnigmaChar::try_from)
+            .collect::<EnigmaResult<Vec<EnigmaChar>>>()?;
+
+        for (rotor, c) in self.rotors.iter_mut().zip(rings) {
+            rotor.set_ring(&c)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the ring settings of the rotors as a three-long array. Index 0 is the left rotor, index 1 is the middle rotor and index 2 is the right rotor.
+    pub fn get_ring(&self) -> [char; 3] {
+        let [l, m, r] = self.lmr() else {
+            unreachable!()
+        };
+
+        [
+            char::from(l.get_ring()),
+            char::from(m.get_ring()),
+            char::from(r.get_ring()),
+        ]
+    }
+
+    /// Returns the ring settings of the rotors as a string, one character per rotor, left to right. An M4 machine's string starts with the Greek rotor.
+    pub fn get_ring_str(&self) -> String {
+        self.rotors
+            .iter()
+            .map(|r| char::from(r.get_ring()))
+            .collect()
+    }
+
+    /// Steps the rotors as if `keypresses` keys were pressed, without encoding anything.
+    ///
+    /// The steps count towards `rewind()` like encoded characters, so advancing can be undone.
+    ///
+    /// # Arguments
+    ///
+    /// * `keypresses` - Number of keypresses to simulate
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!(),
+    /// ).unwrap();
+    ///
+    /// enigma.set_position_str("ADU").unwrap();
+    /// assert_eq!(['B', 'F', 'Y'], enigma.peek_position(4));
+    /// assert_eq!("ADU", enigma.get_position_str());
+    ///
+    /// enigma.advance(4);
+    /// assert_eq!("BFY", enigma.get_position_str());
+    /// ```
+    pub fn advance(&mut self, keypresses: usize) {
+        for _ in 0..keypresses {
+            self.turn_rotors();
+        }
+    }
+
+    /// Returns the positions of the left, middle and right rotor after `keypresses` more keys, in the format of
+    /// `get_position()`. The machine itself isn't moved.
+    ///
+    /// # Arguments
+    ///
+    /// * `keypresses` - Number of keypresses to look ahead
+    pub fn peek_position(&self, keypresses: usize) -> [char; 3] {
+        let mut enigma = self.clone();
+        enigma.advance(keypresses);
+
+        enigma.get_position()
+    }
+
+    /// Returns the number of keypresses since the rotors were last set to a position or reset.
+    ///
+    /// Only encoded letters count; characters that are dropped or passed through as unsupported don't step the rotors.
+    /// Steps simulated with `advance()` count as keypresses.
+    pub fn chars_encoded(&self) -> usize {
+        self.steps
+    }
+
+    /// Returns the number of keypresses after which the rotors are back at their current positions for the first time.
+    ///
+    /// The stepping rotors go through a fixed cycle of positions, 26 × 25 × 26 = 16900 long for three rotors with one
+    /// notch each, as the double-step makes the middle rotor skip a position on every turn of the left one. Positions
+    /// the machine can never step to again, like a middle rotor set onto its notch while the right rotor isn't just
+    /// past its own, return `None`. The machine itself isn't moved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!(),
+    /// ).unwrap();
+    /// assert_eq!(Some(16900), enigma.remaining_before_repeat());
+    ///
+    /// enigma.set_position_str("AEA").unwrap();
+    /// assert_eq!(None, enigma.remaining_before_repeat());
+    /// ```
+    pub fn remaining_before_repeat(&self) -> Option<usize> {
+        let mut enigma = self.clone();
+        let position = |e: &Enigma| {
+            e.rotors[e.fixed..]
+                .iter()
+                .map(|r| r.get_position().internal)
+                .collect::<Vec<u8>>()
+        };
+        let start = position(self);
+
+        // The rotors can't be in more positions than that, so the cycle has to close before
+        let limit = 26usize.saturating_pow(start.len() as u32);
+        for keypresses in 1..=limit {
+            enigma.turn_rotors();
+            if position(&enigma) == start {
+                return Some(keypresses);
+            }
+        }
+
+        None
+    }
+
+    /// Steps the rotors backwards by `keypresses` keypresses.
+    ///
+    /// Because of the double-step, the previous position can't always be derived from the current one. Instead, the
+    /// machine remembers the position set by the most recent `set_position()` or `set_position_str()` call (or "AAA"
+    /// for a new machine) and counts the steps since then. Rewinding moves the rotors back to that start and steps them
+    /// forward again, so it is only possible up to the start: rewinding further returns `EnigmaError::InvalidRewind`
+    /// and leaves the machine untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `keypresses` - Number of keypresses to undo
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!(),
+    /// ).unwrap();
+    ///
+    /// enigma.set_position_str("ADU").unwrap();
+    /// enigma.get_for_str("test", false, true).unwrap();
+    /// assert_eq!("BFY", enigma.get_position_str());
+    ///
+    /// enigma.rewind(4).unwrap();
+    /// assert_eq!("ADU", enigma.get_position_str());
+    /// assert!(enigma.rewind(1).is_err());
+    /// ```
+    pub fn rewind(&mut self, keypresses: usize) -> EnigmaResult<()> {
+        if keypresses > self.steps {
+            return Err(EnigmaError::InvalidRewind(keypresses));
+        }
+
+        let steps = self.steps - keypresses;
+        self.reset();
+        for _ in 0..steps {
+            self.turn_rotors();
+        }
+
+        Ok(())
+    }
+
+    /// Returns the rotors to the positions set by the most recent `set_position()` or `set_position_str()` call, or
+    /// to "AAA" for a new machine. The wirings, ring settings and the plugboard are left untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    /// enigma.set_position_str("FCB").unwrap();
+    ///
+    /// let enc = enigma.get_for_str("test", false, true).unwrap();
+    /// enigma.reset();
+    ///
+    /// assert_eq!("FCB", enigma.get_position_str());
+    /// assert_eq!("test", enigma.get_for_str(&enc, false, true).unwrap());
+    /// ```
+    pub fn reset(&mut self) {
+        for (rotor, pos) in self.rotors.iter_mut().zip(&self.start) {
+            // Recorded positions are always inside the alphabet
+            rotor
+                .set_position(&EnigmaChar {
+                    internal: *pos,
+                    uppercase: true,
+                })
+                .unwrap();
+        }
+
+        self.steps = 0;
+    }
+
+    /// Installs standard rotors specified by their names, left to right, e.g. "I II III".
+    ///
+    /// Every rotor may be used only once and reflectors can't be used as rotors. An M4 machine takes four names, the
+    /// first of which has to be a Greek rotor, e.g. "Beta II IV I". Greek rotors can't be used in any other slot. The
+    /// reflector and the plugboard are kept. All rotors, the Greek rotor of an M4 machine included, are replaced by
+    /// fresh ones at position 'A' with ring setting 'A', and the current positions become the starting ones for
+    /// `reset()`. On error the machine is left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - Whitespace separated names of the rotors, one per slot, case insensitive
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!(),
+    /// ).unwrap();
+    ///
+    /// enigma.set_rotors_by_name("IV V VI").unwrap();
+    /// assert!(enigma.set_rotors_by_name("IV IV VI").is_err());
+    /// ```
+    pub fn set_rotors_by_name(&mut self, names: &str) -> EnigmaResult<()> {
+        let rotors = names
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<EnigmaResult<Vec<StandardWiring>>>()?;
+
+        if rotors.len() != self.rotor_count() {
+            return Err(EnigmaError::InvalidRotorSelection(names.to_owned()));
+        }
+        check_selection(None, &rotors, self.fixed, || names.to_owned())?;
+
+        self.rotors = rotors.into_iter().map(|w| Rotor::new(w.into())).collect();
+
+        self.set_position_str(&"A".repeat(self.rotor_count()))
+    }
+
+    /// Replaces the three stepping rotors with new wirings, left to right.
+    ///
+    /// The new rotors start at position 'A' with ring setting 'A', like freshly inserted ones, and the current
+    /// positions become the starting ones for `reset()`. The reflector, the plugboard and the Greek rotor of an M4
+    /// machine are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `left` - Wiring of the left rotor
+    /// * `middle` - Wiring of the middle rotor
+    /// * `right` - Wiring of the right rotor
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    /// enigma.set_position_str("ADU").unwrap();
+    ///
+    /// enigma.set_rotors(StandardWiring::III.into(), StandardWiring::II.into(), StandardWiring::I.into());
+    ///
+    /// assert_eq!("AAA", enigma.get_position_str());
+    /// assert_eq!("UKW_B | III II I", &enigma.to_string()[7..23]);
+    /// ```
+    pub fn set_rotors(&mut self, left: Wiring, middle: Wiring, right: Wiring) {
+        self.rotors.truncate(self.fixed);
+        self.rotors.extend([left, middle, right].map(Rotor::new));
+
+        self.start = self
+            .rotors
+            .iter()
+            .map(|r| r.get_position().internal)
+            .collect();
+        self.steps = 0;
+    }
+
+    /// Replaces the reflector with a new wiring, keeping the rotors and the plugboard as they are.
+    ///
+    /// The wiring isn't checked, use `Wiring::new_reflector()` to build one that is guaranteed to work as a reflector.
+    ///
+    /// # Arguments
+    ///
+    /// * `ukw` - Wiring of the new reflector
+    pub fn set_reflector(&mut self, ukw: Wiring) {
+        self.ukw = Rotor::new(ukw);
+    }
+
+    /// Connects two letters on the plugboard with a cable.
+    ///
+    /// Plugging a cable that is already there does nothing. Returns `EnigmaError::InvalidSteckerbrettString` if either
+    /// letter is already cabled to a different partner, if both letters are the same, or if all
+    /// `Steckerbrett::MAX_PAIRS` cables are in use. On error the plugboard is left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - First letter, case insensitive
+    /// * `b` - Second letter, case insensitive
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!(),
+    /// ).unwrap();
+    ///
+    /// enigma.plug('A', 'Q').unwrap();
+    /// assert!(enigma.plug('A', 'F').is_err());
+    ///
+    /// enigma.unplug('q').unwrap();
+    /// enigma.plug('A', 'F').unwrap();
+    /// ```
+    pub fn plug(&mut self, a: char, b: char) -> EnigmaResult<()> {
+        let mut pairs = self.steckerbrett.pairs();
+        let (a, b) = (a.to_ascii_uppercase(), b.to_ascii_uppercase());
+
+        if pairs.contains(&(a, b)) || pairs.contains(&(b, a)) {
+            return Ok(());
+        }
+
+        pairs.push((a, b));
+        self.steckerbrett.replace_pairs(&pairs)
+    }
+
+    /// Removes the cable connected to the letter, in both directions. Unplugging a letter without a cable does nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - Letter to disconnect, case insensitive
+    pub fn unplug(&mut self, a: char) -> EnigmaResult<()> {
+        let a = EnigmaChar::try_from(a)?.internal;
+        let b = self.steckerbrett.0[a as usize];
+
+        self.steckerbrett.0[a as usize] = a;
+        self.steckerbrett.0[b as usize] = b;
+
+        Ok(())
+    }
+
+    /// Installs an Uhr in place of the plugboard cables, or removes it with `None`.
+    ///
+    /// While an Uhr is installed, the plugboard cables set up with `plug()` or at construction are not used, but they
+    /// are kept for when it is removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `uhr` - Uhr to install
+    pub fn set_uhr(&mut self, uhr: Option<Uhr>) {
+        self.uhr = uhr;
+    }
+
+    /// Returns the installed Uhr, if any
+    pub fn uhr(&self) -> Option<&Uhr> {
+        self.uhr.as_ref()
+    }
+
+    /// Turns the disc of the installed Uhr to a new position
+    ///
+    /// # Errors
+    /// * `EnigmaError::MissingUhr` - No Uhr is installed
+    /// * `EnigmaError::InvalidUhrDial` - The dial position is 40 or more
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - Position of the disc, 0 to 39
+    pub fn set_uhr_dial(&mut self, n: u8) -> EnigmaResult<()> {
+        self.uhr
+            .as_mut()
+            .ok_or(EnigmaError::MissingUhr)?
+            .set_dial(n)
+    }
+
+    /// Returns the number of rotors of this machine, not counting the reflector. This is 4 for an M4 machine and 3 otherwise.
+    pub fn rotor_count(&self) -> usize {
+        self.rotors.len()
+    }
+
+    /// Sets the rotor's positions specified by a string.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - A string of ascii alphabet characters, one per rotor, each representing a rotor's position. Left to right.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// // Creates an enigma machine
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    ///
+    /// // Sets the left rotor to 'F', middle to 'C' and right to 'B'
+    /// enigma.set_position_str("FCB");
+    ///
+    /// // Encode "test" starting at "FCB"
+    /// let pos_fcb = enigma.get_for_str("test", false, true).unwrap();
+    ///
+    /// // Sets all rotors to 'A'
+    /// enigma.set_position_str("AAA");
+    ///
+    /// // Encode "test" starting at "AAA"
+    /// let pos_aaa = enigma.get_for_str("test", false, true).unwrap();
+    ///
+    /// assert_ne!(pos_fcb, pos_aaa);
+    /// ```
+    pub fn set_position_str(&mut self, position: &str) -> EnigmaResult<()> {
+        if position.chars().count() != self.rotor_count() {
+            return Err(crate::EnigmaError::InvalidPositionLength(
+                position.to_owned(),
+                self.rotor_count(),
+            ));
+        }
+
+        let positions = position
+            .chars()
+            .map(EnigmaChar::try_from)
+            .collect::<EnigmaResult<Vec<EnigmaChar>>>()?;
+
+        for (rotor, c) in self.rotors.iter_mut().zip(positions) {
+            rotor.set_position(&c)?;
+        }
+
+        self.mark_start();
+
+        Ok(())
+    }
+
+    /// Returns true if both machines have the same reflector and the same rotor wirings and notches in the same slots.
+    ///
+    /// Rotor positions are ignored, as is the plugboard, so two machines that compare equal are the same machine,
+    /// possibly set to a different key.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Machine to compare with
+    pub fn structurally_eq(&self, other: &Enigma) -> bool {
+        self.ukw.wiring() == other.ukw.wiring()
+            && self.fixed == other.fixed
+            && self.rotors.len() == other.rotors.len()
+            && self
+                .rotors
+                .iter()
+                .zip(&other.rotors)
+                .all(|(a, b)| a.wiring() == b.wiring())
+    }
+
+    #[cfg(feature = "std")]
+    /// Returns a hash of the structure of this machine: the reflector, the rotor wirings and notches in their slots, and the plugboard.
+    ///
+    /// Rotor positions are intentionally excluded, so two machines that differ only in their current position share
+    /// a hash. This makes it possible to skip equivalent configurations in large searches. The hash is stable within
+    /// a build, but not across Rust versions.
+    pub fn structure_hash(&self) -> u64 {
+        use core::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.ukw.wiring().hash(&mut hasher);
+        self.fixed.hash(&mut hasher);
+        for rotor in &self.rotors {
+            rotor.wiring().hash(&mut hasher);
+        }
+        self.steckerbrett.hash(&mut hasher);
+        self.uhr.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// Returns a snapshot of the complete configuration of this machine: reflector, rotors, positions, ring settings and plugboard.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    ///
+    /// let config = enigma.config();
+    ///
+    /// assert_eq!(config.reflector.name, Some(StandardWiring::UKW_B));
+    /// assert_eq!(config.rotors[2].wiring, "BDFHJLCPRTXVZNYEIWGAKMUSQO");
+    /// assert_eq!(config.positions, "AAA");
+    /// assert_eq!(config.rings, "AAA");
+    /// assert_eq!(config.plugboard, vec![('A', 'Q'), ('F', 'R'), ('M', 'S')]);
+    /// ```
+    pub fn config(&self) -> EnigmaConfig {
+        EnigmaConfig::from(self)
+    }
+
+    /// Returns a snapshot of the complete configuration of this machine. Same as `config()`, the counterpart of `from_config()`.
+    pub fn to_config(&self) -> EnigmaConfig {
+        self.config()
+    }
+
+    /// Creates a machine from a configuration snapshot, as returned by `to_config()`.
+    ///
+    /// Rotors and the reflector are built from their standard wiring if `name` is set, and from `wiring` and
+    /// `notches` otherwise. The `fixed` leftmost rotors don't step, which makes a machine with four rotors and one fixed
+    /// rotor an M4, with the Greek rotor first.
+    ///
+    /// # Errors
+    ///
+    /// * `EnigmaError::InvalidWiringName` - A custom wiring isn't 26 letters long
+    /// * `EnigmaError::InvalidRotorSelection` - There are fewer than three rotors besides the fixed ones
+    /// * `EnigmaError::InvalidSteckerbrettString` - A letter is plugged to itself or more than once
+    /// * `EnigmaError::InvalidPositionLength` - Positions or rings don't have one letter per rotor
+    /// * `EnigmaError::InvalidChar` - A wiring, position or ring contains a non-letter
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration to build the machine from
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    /// enigma.set_ring_str("BUL").unwrap();
+    /// enigma.set_position_str("FCB").unwrap();
+    ///
+    /// let mut copy = Enigma::from_config(&enigma.to_config()).unwrap();
+    ///
+    /// assert_eq!(
+    ///     enigma.get_for_str("test", false, true).unwrap(),
+    ///     copy.get_for_str("test", false, true).unwrap()
+    /// );
+    /// ```
+    pub fn from_config(config: &EnigmaConfig) -> EnigmaResult<Self> {
+        Self::try_from(config)
+    }
+
+    /// Creates a machine from a key written as a single line, e.g. "B I II III AAA FCB AQ FR SM".
+    ///
+    /// The whitespace separated fields are the reflector, the rotors from left to right, the ring settings, the rotor
+    /// positions and then any number of plugboard pairs. Names are matched case insensitively and the reflector may
+    /// leave out the "UKW_" prefix. A thin reflector makes the machine an M4, with the Greek rotor as the first of four
+    /// rotors, e.g. "B_Thin Beta II IV I AAAA VJNA".
+    ///
+    /// An Uhr is installed by ending the key with its dial position and its ten pairs, a-plug first, in the order of
+    /// the cables, e.g. "B I II III AAA AAA uhr=27 AB CD EF GH IJ KL MN OP QR ST".
+    ///
+    /// # Errors
+    ///
+    /// * `EnigmaError::InvalidSettings` - A field before the plugboard pairs is missing, or the Uhr dial isn't a number
+    /// * `EnigmaError::InvalidWiringName` - The reflector or a rotor isn't a standard wiring
+    /// * `EnigmaError::InvalidRotorSelection` - The reflector isn't one, or a rotor is used twice or doesn't fit its slot
+    /// * `EnigmaError::InvalidPositionLength` - Rings or positions don't have one letter per rotor
+    /// * `EnigmaError::InvalidChar` - Rings or positions contain a non-letter
+    /// * `EnigmaError::InvalidPosition` - A plugboard pair isn't two letters
+    /// * `EnigmaError::InvalidSteckerbrettString` - A letter is plugged to itself or more than once, or the Uhr doesn't
+    ///   have 10 pairs
+    /// * `EnigmaError::InvalidUhrDial` - The Uhr dial position is 40 or more
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - Key to set up
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::Enigma;
+    ///
+    /// let mut enigma = Enigma::from_settings("B I II III AAA AAA AQ FR SM").unwrap();
+    ///
+    /// assert_eq!("olkr", enigma.get_for_str("test", false, true).unwrap());
+    /// assert_eq!("B I II III AAA AAE AQ FR MS", enigma.to_settings());
+    /// ```
+    pub fn from_settings(settings: &str) -> EnigmaResult<Self> {
+        let mut fields = settings.split_whitespace();
+        let mut field = || {
+            fields
+                .next()
+                .ok_or_else(|| EnigmaError::InvalidSettings(settings.to_owned()))
+        };
+
+        let reflector = field()?;
+        let ukw = reflector
+            .parse::<StandardWiring>()
+            .or_else(|_| format!("UKW_{}", reflector).parse())
+            .map_err(|_| EnigmaError::InvalidWiringName(reflector.to_owned()))?;
+
+        let m4 = matches!(ukw, StandardWiring::UKW_B_Thin | StandardWiring::UKW_C_Thin);
+        let rotors = (0..if m4 { 4 } else { 3 })
+            .map(|_| field()?.parse())
+            .collect::<EnigmaResult<Vec<StandardWiring>>>()?;
+        let rings = field()?;
+        let positions = field()?;
+
+        let rest = fields.collect::<Vec<&str>>();
+        let (cables, uhr) = match rest
+            .iter()
+            .position(|f| f.get(..4).is_some_and(|p| p.eq_ignore_ascii_case("uhr=")))
+        {
+            Some(i) => (&rest[..i], Some(&rest[i..])),
+            None => (&rest[..], None),
+        };
+
+        let stecker = Steckerbrett::try_from(cables.join(" ").as_str())?;
+        let mut enigma = match rotors[..] {
+            [l, m, r] => Self::standard(ukw, l, m, r, stecker)?,
+            [greek, l, m, r] => Self::m4(ukw, greek, l, m, r, stecker)?,
+            _ => unreachable!(),
+        };
+
+        enigma.set_ring_str(rings)?;
+        enigma.set_position_str(positions)?;
+
+        if let Some([dial, pairs @ ..]) = uhr {
+            let dial = dial[4..]
+                .parse()
+                .map_err(|_| EnigmaError::InvalidSettings(settings.to_owned()))?;
+            let pairs = pairs
+                .iter()
+                .map(|p| match p.chars().collect::<Vec<char>>()[..] {
+                    [a, b] => Ok((a, b)),
+                    _ => Err(EnigmaError::InvalidPosition((*p).to_owned())),
+                })
+                .collect::<EnigmaResult<Vec<(char, char)>>>()?;
+            enigma.uhr = Some(Uhr::new(&pairs, dial)?);
+        }
+
+        Ok(enigma)
+    }
+
+    /// Returns the key of this machine as a single line, in the format accepted by `from_settings()`.
+    ///
+    /// The current rotor positions are written, not the starting ones. Custom wirings are written as their template,
+    /// which `from_settings()` doesn't accept. An installed Uhr is written after the plugboard pairs, which are kept
+    /// for when it is removed.
+    pub fn to_settings(&self) -> String {
+        let reflector = wiring_name(self.ukw.wiring());
+        let mut out = String::from(reflector.strip_prefix("UKW_").unwrap_or(&reflector));
+
+        for rotor in &self.rotors {
+            out.push(' ');
+            out.push_str(&wiring_name(rotor.wiring()));
+        }
+        out.push(' ');
+        out.push_str(&self.get_ring_str());
+        out.push(' ');
+        out.push_str(&self.get_position_str());
+
+        if !self.steckerbrett.pairs().is_empty() {
+            out.push(' ');
+            out.push_str(&self.steckerbrett.to_string());
+        }
+
+        if let Some(uhr) = &self.uhr {
+            out.push_str(&format!(" uhr={:02}", uhr.dial()));
+            for (a, b) in uhr.pairs() {
+                out.push_str(&format!(" {}{}", a, b));
+            }
+        }
+
+        out
+    }
+
+    /// Returns the configuration of this machine in terms of standard wirings, as accepted by `Enigma::standard()`.
+    ///
+    /// The returned tuple holds the reflector, the left, middle and right rotor, the current position as a string and the plugboard.
+    /// Returns `None` if the reflector or any of the rotors uses a custom wiring, or if the machine doesn't have exactly three rotors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q').unwrap(),
+    /// ).unwrap();
+    /// enigma.set_position_str("FCB").unwrap();
+    ///
+    /// let (ukw, [l, m, r], pos, stecker) = enigma.as_standard_config().unwrap();
+    /// let mut copy = Enigma::standard(ukw, l, m, r, stecker).unwrap();
+    /// copy.set_position_str(&pos).unwrap();
+    ///
+    /// assert_eq!(
+    ///     enigma.get_for_str("test", false, true).unwrap(),
+    ///     copy.get_for_str("test", false, true).unwrap()
+    /// );
+    /// ```
+    pub fn as_standard_config(
+        &self,
+    ) -> Option<(StandardWiring, [StandardWiring; 3], String, Steckerbrett)> {
+        let [l, m, r] = self.rotors.as_slice() else {
+            return None;
+        };
+
+        Some((
+            StandardWiring::identify(self.ukw.wiring())?,
+            [
+                StandardWiring::identify(l.wiring())?,
+                StandardWiring::identify(m.wiring())?,
+                StandardWiring::identify(r.wiring())?,
+            ],
+            self.get_position_str(),
+            self.steckerbrett.clone(),
+        ))
+    }
+
+    /// Returns the positions of the rotors as a three-long array. Index 0 is the left rotor, index 1 is the middle rotor and index 2 is the right rotor.
+    pub fn get_position(&self) -> [char; 3] {
+        let [l, m, r] = self.lmr() else {
+            unreachable!()
+        };
+
+        [
+            char::from(l.get_position()),
+            char::from(m.get_position()),
+            char::from(r.get_position()),
+        ]
+    }
+
+    /// Returns the position of the rotors as a string, one character per rotor, left to right. An M4 machine's string starts with the Greek rotor.
+    pub fn get_position_str(&self) -> String {
+        self.rotors
+            .iter()
+            .map(|r| char::from(r.get_position()))
+            .collect()
+    }
+
+    /// Returns the ring settings of the rotors as a three-long array of numbers in the range 1..=26, as used on key sheets. Index 0 is the left rotor, index 1 is the middle rotor and index 2 is the right rotor.
+    pub fn get_ring_numeric(&self) -> [u8; 3] {
+        let [l, m, r] = self.lmr() else {
+            unreachable!()
+        };
+
+        [
+            l.get_ring().internal + 1,
+            m.get_ring().internal + 1,
+            r.get_ring().internal + 1,
+        ]
+    }
+
+    /// Returns the positions of the rotors as a three-long array of numbers in the range 1..=26, as used on key sheets. Index 0 is the left rotor, index 1 is the middle rotor and index 2 is the right rotor.
+    pub fn get_position_numeric(&self) -> [u8; 3] {
+        let [l, m, r] = self.lmr() else {
+            unreachable!()
+        };
+
+        [
+            l.get_position().internal + 1,
+            m.get_position().internal + 1,
+            r.get_position().internal + 1,
+        ]
+    }
+
+    /// Returns which rotors will advance on the next keypress, computed from the current positions and notches.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!(),
+    /// ).unwrap();
+    ///
+    /// // The middle rotor is on its notch, so the next keypress double-steps
+    /// enigma.set_position_str("AEA").unwrap();
+    /// let next = enigma.next_step_info();
+    ///
+    /// assert!(next.left && next.middle && next.double_step);
+    /// ```
+    pub fn next_step_info(&self) -> NextStep {
+        let [_, m, r] = self.lmr() else {
+            unreachable!()
+        };
+        let notch_r = r.has_notch();
+        let notch_m = m.has_notch();
+
+        NextStep {
+            left: notch_m,
+            middle: notch_r || notch_m,
+            right: true,
+            double_step: notch_m,
+        }
+    }
+
+    /// Returns whether the left, middle and right rotor are currently on one of their turnover notches.
+    ///
+    /// The notches are on the alphabet rings, so they are reported for the visible positions whatever the ring
+    /// settings are. A rotor on its notch steps its left neighbour on the next keypress, see `next_step_info()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!(),
+    /// )
+    /// .unwrap();
+    ///
+    /// enigma.set_position_str("QEA").unwrap();
+    /// assert_eq!([true, true, false], enigma.notch_state());
+    /// ```
+    pub fn notch_state(&self) -> [bool; 3] {
+        let [l, m, r] = self.lmr() else {
+            unreachable!()
+        };
+
+        [l.has_notch(), m.has_notch(), r.has_notch()]
+    }
+
+    /// Returns true if the next keypress will double-step, i.e. advance the middle rotor because of its own notch and
+    /// take the left rotor with it. Same as `next_step_info().double_step`.
+    pub fn will_double_step(&self) -> bool {
+        self.next_step_info().double_step
+    }
+
+    /// Rotates the rotors by one step
+    fn turn_rotors(&mut self) {
+        let bank = &mut self.rotors[self.fixed..];
+        let fast = bank.len() - 1;
+
+        // Every pawl that drops into a notch pushes the rotor to its left and, unless it is the right one, the notched
+        // rotor itself. A rotor pushed by two pawls at once still only moves by one step. Going from left to right,
+        // each decision only looks at rotors that haven't moved yet.
+        for i in 0..bank.len() {
+            let step = i == fast || bank[i + 1].has_notch() || (i != 0 && bank[i].has_notch());
+            if step {
+                bank[i].rotate();
+            }
+        }
+
+        self.steps += 1;
+    }
+
+    /// Runs a single character through the machine
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - Character to encode
+    pub fn get_for_char(&mut self, c: char) -> EnigmaResult<char> {
+        self._internal_get_for_char(c).map(char::from)
+    }
+
+    /// Returns a handle for encoding single characters in hot loops.
+    ///
+    /// The handle caches the parts of the signal path that don't change on every keypress. It borrows the machine
+    /// mutably and must be dropped before the machine is used normally again; the rotors are left exactly where
+    /// `get_for_char()` would have left them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    ///
+    /// let enc: String = {
+    ///     let mut fast = enigma.prepare_fast();
+    ///     "test".chars().map(|c| fast.encode_char(c).unwrap()).collect()
+    /// };
+    ///
+    /// assert_eq!("olkr", enc);
+    /// assert_eq!("AAE", enigma.get_position_str());
+    /// ```
+    pub fn prepare_fast(&mut self) -> FastEncoder<'_> {
+        FastEncoder::new(self)
+    }
+
+    /// Encodes `seed`, then feeds every output character back in as the next input, returning the `count` produced characters.
+    ///
+    /// This is an experimentation toy that shows the dynamics and period of the machine. It does not correspond to any
+    /// historical operating procedure.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - First character to encode
+    /// * `count` - Number of characters to produce
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!(),
+    /// ).unwrap();
+    ///
+    /// let chain = enigma.encode_autokey('A', 5).unwrap();
+    ///
+    /// assert_eq!(chain.len(), 5);
+    /// ```
+    pub fn encode_autokey(&mut self, seed: char, count: usize) -> EnigmaResult<String> {
+        let mut out = String::with_capacity(count);
+        let mut c = seed;
+
+        for _ in 0..count {
+            c = self.get_for_char(c)?;
+            out.push(c);
+        }
+
+        Ok(out)
+    }
+
+    /// Actually runs a single character through a machine, only difference being that this method returns an ```EnigmaCharacter```.
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - Character to encode
+    fn _internal_get_for_char(&mut self, c: char) -> EnigmaResult<EnigmaChar> {
+        let mut c = {
+            let x = EnigmaChar::try_from(c);
+            if let Err(EnigmaError::InvalidChar(c)) = x {
+                return Err(EnigmaError::UnsupportedCharacter(c));
+            }
+
+            x?
+        };
+
+        self.turn_rotors();
+        self.transform(&mut c)?;
+
+        Ok(c)
+    }
+
+    /// Runs a character through the plugboard, rotors and reflector at the current positions, without stepping the rotors
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - Character to encode
+    fn transform(&self, c: &mut EnigmaChar) -> EnigmaResult<()> {
+        self.plugboard(c, false);
+        for rotor in self.rotors.iter().rev() {
+            rotor.get_for(c, false)?;
+        }
+
+        self.ukw.get_for(c, false)?;
+
+        for rotor in self.rotors.iter() {
+            rotor.get_for(c, true)?;
+        }
+        self.plugboard(c, true);
+
+        Ok(())
+    }
+
+    /// Runs a character through the Uhr if one is installed, or through the plugboard cables otherwise
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - Character to run through
+    /// * `reversed` - Whether the signal is travelling back from the rotors to the lamps
+    pub(crate) fn plugboard(&self, c: &mut EnigmaChar, reversed: bool) {
+        match &self.uhr {
+            Some(uhr) => uhr.get(c, reversed),
+            None => self.steckerbrett.get(c),
+        }
+    }
+
+    /// Returns the substitution the machine performs at the current rotor positions, without the step that precedes every keypress.
+    ///
+    /// Index `i` holds the alphabet position that the `i`-th letter of the alphabet is encoded to. This makes the
+    /// result usable like the wiring table of a single rotor. As every keypress steps the rotors first, the next
+    /// letter typed is encoded with the permutation of the following position, not this one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!(),
+    /// ).unwrap();
+    /// enigma.set_position_str("AAB").unwrap();
+    /// let permutation = enigma.current_permutation();
+    ///
+    /// enigma.set_position_str("AAA").unwrap();
+    /// let c = enigma.get_for_char('T').unwrap();
+    ///
+    /// assert_eq!((b'A' + permutation[(b'T' - b'A') as usize]) as char, c);
+    /// ```
+    pub fn current_permutation(&self) -> [u8; 26] {
+        let mut mapping = [0u8; 26];
+
+        for (i, m) in mapping.iter_mut().enumerate() {
+            let mut c = EnigmaChar {
+                internal: i as u8,
+                uppercase: true,
+            };
+            // Rotor and plugboard lookups cannot fail for characters inside the alphabet
+            self.transform(&mut c).unwrap();
+            *m = c.internal;
+        }
+
+        mapping
+    }
+
+    /// Returns a letter that would be encoded to itself at the current rotor positions, or `None` if there is none.
+    ///
+    /// Like `current_permutation()`, the rotors are not stepped first. A proper Enigma never encodes a letter to
+    /// itself, so a returned letter points to a faulty custom reflector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::{StandardWiring, Wiring}};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!(),
+    /// ).unwrap();
+    /// assert_eq!(None, enigma.encodes_to_self());
+    ///
+    /// // Not a reflector: the alphabet is wired straight through
+    /// let straight: Vec<char> = ('A'..='Z').collect();
+    /// enigma.set_reflector(Wiring::new(straight.try_into().unwrap(), &[]).unwrap());
+    /// assert_eq!(Some('A'), enigma.encodes_to_self());
+    /// ```
+    pub fn encodes_to_self(&self) -> Option<char> {
+        self.current_permutation()
+            .iter()
+            .enumerate()
+            .find(|(i, m)| *i as u8 == **m)
+            .map(|(i, _)| (b'A' + i as u8) as char)
+    }
+
+    /// Returns true if decrypting the encrypted `sample` gives back the sample, both done from the start position.
+    ///
+    /// This holds for every machine with a proper reflector. The machine is reset before and after the check, see
+    /// `reset()`. Characters outside of the alphabet are kept as they are.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample` - Text to encrypt and decrypt
+    pub fn is_reciprocal(&mut self, sample: &str) -> bool {
+        self.reset();
+        let decrypted = self.get_for_str(sample, true, true).and_then(|enc| {
+            self.reset();
+            self.get_for_str(&enc, true, true)
+        });
+        self.reset();
+
+        decrypted.is_ok_and(|d| d == sample)
+    }
+
+    /// Returns the positions of the right rotor at which some letter would be encoded to itself.
+    ///
+    /// The right rotor is moved through all 26 positions while the other rotors stay where they are, and the full
+    /// signal path is checked at every position without stepping. A proper Enigma never encodes a letter to itself,
+    /// so any returned position points to a faulty custom reflector. The machine itself is not modified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!(),
+    /// ).unwrap();
+    ///
+    /// assert!(enigma.self_encoding_positions().is_empty());
+    /// ```
+    pub fn self_encoding_positions(&self) -> Vec<char> {
+        let mut machine = self.clone();
+
+        (0..26)
+            .map(|i| EnigmaChar {
+                internal: i,
+                uppercase: true,
+            })
+            .filter(|pos| {
+                // Setting the position of a single rotor cannot fail
+                machine
+                    .rotors
+                    .last_mut()
+                    .unwrap()
+                    .set_position(pos)
+                    .unwrap();
+                machine.encodes_to_self().is_some()
+            })
+            .map(char::from)
+            .collect()
+    }
+
+    /// Returns the substitution the machine performs at the current rotor positions in cycle notation, e.g. "(AB)(CX)...".
+    ///
+    /// Cycles are listed by their smallest letter. Letters that map to themselves are written as one-letter cycles,
+    /// which never happens with a proper reflector. As every standard Enigma substitution is an involution, the result
+    /// consists of thirteen 2-cycles. The rotors are not stepped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!(),
+    /// ).unwrap();
+    ///
+    /// let cycles = enigma.mapping_cycles();
+    ///
+    /// assert_eq!(cycles.matches('(').count(), 13);
+    /// ```
+    pub fn mapping_cycles(&self) -> String {
+        let mapping = self.current_permutation();
+        let mut visited = [false; 26];
+        let mut out = String::new();
+
+        for start in 0..26 {
+            if visited[start] {
+                continue;
+            }
+
+            out.push('(');
+            let mut i = start;
+            while !visited[i] {
+                visited[i] = true;
+                out.push(char::from(EnigmaChar {
+                    internal: i as u8,
+                    uppercase: true,
+                }));
+                i = mapping[i] as usize;
+            }
+            out.push(')');
+        }
+
+        out
+    }
+
+    /// Encodes a string using this enigma machine.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to encrypt
+    /// * `preserve_unsupported` - Whether non-alphabet characters should be preserved in the output
+    /// * `preserve_case` - Whether output characters should match the case of the input characters
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// // Creates an enigma machine
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    ///
+    /// /// Encode "test"
+    /// let enc = enigma.get_for_str("test", false, true).unwrap();
+    ///
+    /// assert_eq!("olkr", enc);
+    /// ```
+    pub fn get_for_str(
+        &mut self,
+        str: &str,
+        preserve_unsupported: bool,
+        preserve_case: bool,
+    ) -> EnigmaResult<String> {
+        let mut out = String::with_capacity(str.len());
+        self.encode_into(str, &mut out, preserve_unsupported, preserve_case)?;
+
+        Ok(out)
+    }
+
+    /// Encodes a string like `get_for_str()`, appending the result to an existing buffer instead of allocating a new
+    /// one.
+    ///
+    /// The buffer isn't cleared, so it can be reused across calls by clearing it in between. If encoding fails, the
+    /// characters encoded before the error are left in it.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - String to encrypt
+    /// * `out` - String to append the result to
+    /// * `preserve_unsupported` - Whether non-alphabet characters should be preserved in the output
+    /// * `preserve_case` - Whether output characters should match the case of the input characters
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// )
+    /// .unwrap();
+    ///
+    /// let mut out = String::from("> ");
+    /// enigma.encode_into("test", &mut out, false, true).unwrap();
+    ///
+    /// assert_eq!("> olkr", out);
+    /// ```
+    pub fn encode_into(
+        &mut self,
+        input: &str,
+        out: &mut String,
+        preserve_unsupported: bool,
+        preserve_case: bool,
+    ) -> EnigmaResult<()> {
+        self._internal_encode_into(
+            input,
+            out,
+            EncodeOptions::new(preserve_unsupported, preserve_case),
+            0,
+            |_| {},
+        )
+    }
+
+    /// Encodes a byte buffer using this enigma machine, without decoding it as text first.
+    ///
+    /// Every byte is treated like the `char` of the same value in `get_for_str()`: ASCII letters are encrypted and all
+    /// other bytes, including the letters of Latin-1 above 0x7F, are dropped or preserved as they are. The rotors step
+    /// exactly as with `get_for_str()` on the same ASCII text.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Bytes to encrypt
+    /// * `preserve_unsupported` - Whether non-letter bytes should be preserved in the output
+    /// * `preserve_case` - Whether output letters should match the case of the input letters
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(b"ol kr", enigma.get_for_bytes(b"te st", true, true).unwrap().as_slice());
+    /// ```
+    pub fn get_for_bytes(
+        &mut self,
+        input: &[u8],
+        preserve_unsupported: bool,
+        preserve_case: bool,
+    ) -> EnigmaResult<Vec<u8>> {
+        let mut out = Vec::with_capacity(input.len());
+        let mut fast = FastEncoder::new(self);
+
+        for b in input {
+            if !b.is_ascii_alphabetic() {
+                if preserve_unsupported {
+                    out.push(*b);
+                }
+                continue;
+            }
+
+            let mut c = fast.encode(*b as char)?;
+            if !preserve_case {
+                c.uppercase = true;
+            }
+            out.push(char::from(c) as u8);
+        }
+
+        Ok(out)
+    }
+
+    /// Encodes characters lazily as they are pulled from the returned iterator, one output per input character.
+    ///
+    /// Letters are encrypted keeping their case, while any other character yields `EnigmaError::UnsupportedCharacter`
+    /// without stepping the rotors, leaving it to the caller to skip or preserve it. The iterator borrows the machine
+    /// mutably and steps its rotors as it is consumed, so afterwards the machine stands wherever the last character
+    /// pulled left it. Characters that are never pulled don't step the rotors.
+    ///
+    /// # Arguments
+    ///
+    /// * `iter` - Characters to encrypt
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// )
+    /// .unwrap();
+    ///
+    /// let enc: String = enigma.encode_iter("te st".chars()).filter_map(Result::ok).collect();
+    /// assert_eq!("olkr", enc);
+    /// ```
+    pub fn encode_iter<'a, I: Iterator<Item = char> + 'a>(
+        &'a mut self,
+        iter: I,
+    ) -> impl Iterator<Item = EnigmaResult<char>> + 'a {
+        let mut fast = FastEncoder::new(self);
+
+        iter.map(move |c| fast.encode_char(c))
+    }
+
+    /// Encodes a string like `get_for_str(input, preserve_unsupported, true)` and records every keypress.
+    ///
+    /// Each entry holds the positions of the left, middle and right rotor after stepping, in the format of
+    /// `get_position()`, the input character and the output character. Unsupported characters don't step the rotors;
+    /// they are left out, or recorded with the positions unchanged and the input as the output if
+    /// `preserve_unsupported` is set. A letter is never encoded to itself, so such an entry can't be mistaken for an
+    /// encoded one.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - String to encrypt
+    /// * `preserve_unsupported` - Whether non-alphabet characters should be recorded
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// )
+    /// .unwrap();
+    ///
+    /// let trace = enigma.encode_with_trace("te st", true).unwrap();
+    ///
+    /// assert_eq!((['A', 'A', 'B'], 't', 'o'), trace[0]);
+    /// assert_eq!((['A', 'A', 'C'], ' ', ' '), trace[2]);
+    /// assert_eq!((['A', 'A', 'E'], 't', 'r'), trace[4]);
+    /// ```
+    pub fn encode_with_trace(
+        &mut self,
+        input: &str,
+        preserve_unsupported: bool,
+    ) -> EnigmaResult<Vec<([char; 3], char, char)>> {
+        let mut trace = Vec::with_capacity(input.len());
+
+        for c in input.chars() {
+            match self._internal_get_for_char(c) {
+                Ok(out) => trace.push((self.get_position(), c, char::from(out))),
+                Err(EnigmaError::UnsupportedCharacter(_)) => {
+                    if preserve_unsupported {
+                        trace.push((self.get_position(), c, c));
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(trace)
+    }
+
+    /// Encodes a string the way it was transmitted: uppercase letters only, in groups separated by spaces.
+    ///
+    /// Unsupported characters are dropped, as with `get_for_str(str, false, false)`. The last group is left short if
+    /// the text doesn't divide evenly. A `group_size` of 0 disables grouping.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to encrypt
+    /// * `group_size` - Number of letters in a group, usually 5 (or 4 for Kriegsmarine messages)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    ///
+    /// assert_eq!("OLKR", enigma.get_for_str_grouped("te st", 5).unwrap());
+    /// ```
+    pub fn get_for_str_grouped(&mut self, str: &str, group_size: usize) -> EnigmaResult<String> {
+        let out = self.get_for_str(str, false, false)?;
+
+        Ok(match group_size {
+            0 => out,
+            size => group_letters(&out, size),
+        })
+    }
+
+    /// Replays a recorded log of keypresses, returning the lamps that lit up.
+    ///
+    /// Every letter of `keys` is one keypress, in either case. Characters outside of the alphabet have no key on the
+    /// machine and are skipped without stepping the rotors. The output is always uppercase, like the lampboard.
+    /// This is `get_for_str(keys, false, false)` under a name that reads well in state-machine tests, where a cloned
+    /// machine serves as the snapshot to replay from.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - Recorded keypresses
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    ///
+    /// let snapshot = enigma.clone();
+    /// let first = enigma.replay("te st").unwrap();
+    ///
+    /// let mut enigma = snapshot;
+    /// assert_eq!(first, enigma.replay("TEST").unwrap());
+    /// assert_eq!("OLKR", first);
+    /// ```
+    pub fn replay(&mut self, keys: &str) -> EnigmaResult<String> {
+        self.get_for_str(keys, false, false)
+    }
+
+    /// Sets the rotors to `start`, encodes `plaintext` and checks whether the result equals `ciphertext`.
+    ///
+    /// Both texts are compared as uppercase letters only, so test vectors written in groups or in lowercase can be used as published.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Starting position of the rotors, as accepted by `set_position_str()`
+    /// * `plaintext` - Text to encode
+    /// * `ciphertext` - Expected result
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    ///
+    /// assert!(enigma.matches_vector("AAA", "test", "OLKR").unwrap());
+    /// ```
+    pub fn matches_vector(
+        &mut self,
+        start: &str,
+        plaintext: &str,
+        ciphertext: &str,
+    ) -> EnigmaResult<bool> {
+        self.set_position_str(start)?;
+
+        let enc = self.get_for_str(plaintext, false, false)?;
+        let expected = ciphertext
+            .chars()
+            .filter(char::is_ascii_alphabetic)
+            .map(|c| c.to_ascii_uppercase());
+
+        Ok(enc.chars().eq(expected))
+    }
+
+    /// Prepares a complete transmission of `plaintext` using the message key procedure.
+    ///
+    /// The current position of `day_key` is used as the ground setting (Grundstellung). The message key is encrypted at
+    /// the ground setting to form the indicator, then the machine is set to the message key and the plaintext is
+    /// encrypted. The result is the indicator followed by the ciphertext in groups of five letters, e.g. "QWE ABCDE FGH".
+    ///
+    /// The plaintext is uppercased and characters outside of the alphabet are dropped. `day_key` is not modified.
+    ///
+    /// # Arguments
+    ///
+    /// * `day_key` - Machine set up with the daily key, positioned at the ground setting
+    /// * `message_key` - Starting position for the message body, chosen by the operator
+    /// * `plaintext` - Message to encrypt
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut day_key = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    /// day_key.set_position_str("JKL").unwrap();
+    ///
+    /// let transmission = Enigma::prepare_transmission(&day_key, "XYZ", "Angriff um Mitternacht").unwrap();
+    /// let plaintext = Enigma::receive_transmission(&day_key, &transmission).unwrap();
+    ///
+    /// assert_eq!("ANGRIFFUMMITTERNACHT", plaintext);
+    /// ```
+    pub fn prepare_transmission(
+        day_key: &Enigma,
+        message_key: &str,
+        plaintext: &str,
+    ) -> EnigmaResult<String> {
+        let mut body = day_key.clone();
+        body.set_position_str(message_key)?;
+
+        let indicator = day_key.clone().get_for_str(message_key, false, false)?;
+        let body = body.get_for_str(plaintext, false, false)?;
+
+        Ok(format!("{} {}", indicator, group_letters(&body, 5)))
+    }
+
+    /// Decrypts a transmission produced by `prepare_transmission()`.
+    ///
+    /// The first whitespace separated token is taken as the indicator and decrypted at the current position of
+    /// `day_key` to recover the message key. The rest of the transmission is decrypted starting at the message key,
+    /// ignoring the grouping. `day_key` is not modified.
+    ///
+    /// # Arguments
+    ///
+    /// * `day_key` - Machine set up with the daily key, positioned at the ground setting
+    /// * `transmission` - Indicator followed by the ciphertext
+    pub fn receive_transmission(day_key: &Enigma, transmission: &str) -> EnigmaResult<String> {
+        let transmission = transmission.trim_start();
+        let (indicator, body) = transmission
+            .split_once(char::is_whitespace)
+            .unwrap_or((transmission, ""));
+
+        let mut machine = day_key.clone();
+        machine.recover_message_key(&day_key.get_position_str(), indicator)?;
+        machine.get_for_str(body, false, false)
+    }
+
+    /// Decrypts an indicator at the ground setting and sets the rotors to the recovered message key, which is returned.
+    ///
+    /// This is the receiving half of the indicator procedure: afterwards the machine is ready to decrypt the message body.
+    ///
+    /// # Arguments
+    ///
+    /// * `ground` - Ground setting (Grundstellung) the indicator was encrypted at
+    /// * `encoded_indicator` - Encrypted message key
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    ///
+    /// // Sender
+    /// enigma.set_position_str("JKL").unwrap();
+    /// let indicator = enigma.get_for_str("XYZ", false, false).unwrap();
+    /// enigma.set_position_str("XYZ").unwrap();
+    /// let body = enigma.get_for_str("test", false, false).unwrap();
+    ///
+    /// // Receiver
+    /// assert_eq!("XYZ", enigma.recover_message_key("JKL", &indicator).unwrap());
+    /// assert_eq!("TEST", enigma.get_for_str(&body, false, false).unwrap());
+    /// ```
+    pub fn recover_message_key(
+        &mut self,
+        ground: &str,
+        encoded_indicator: &str,
+    ) -> EnigmaResult<String> {
+        self.set_position_str(ground)?;
+
+        let message_key = self.encode_with_options(
+            encoded_indicator,
+            EncodeOptions {
+                unsupported: UnsupportedPolicy::Error,
+                ..EncodeOptions::default()
+            },
+        )?;
+        self.set_position_str(&message_key)?;
+
+        Ok(message_key)
+    }
+
+    /// Encrypts a message with the indicator procedure, returning the encrypted message key and the encrypted body.
+    ///
+    /// The message key is encrypted at the ground setting (Grundstellung) to form the indicator, then the rotors are
+    /// set to the message key and the plaintext is encrypted, uppercased and with characters outside of the alphabet
+    /// dropped. Afterwards the message key is the starting position of the machine, so `reset()` returns to it, and the
+    /// rotors stand where the last letter of the body left them.
+    ///
+    /// # Errors
+    /// * `EnigmaError::InvalidPositionLength` - The ground setting or the message key doesn't have one letter per
+    ///   rotor, i.e. three on the standard machines
+    /// * `EnigmaError::UnsupportedCharacter` - The message key contains a character outside of the alphabet
+    ///
+    /// # Arguments
+    /// * `ground_setting` - Ground setting to encrypt the message key at
+    /// * `message_key` - Starting position for the message body, chosen by the operator
+    /// * `plaintext` - Message to encrypt
+    ///
     /// # Examples
     ///
     /// ```
     /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
     ///
-    /// // Creates an enigma machine
     /// let mut enigma = Enigma::standard(
     ///     StandardWiring::UKW_B,
     ///     StandardWiring::I,
     ///     StandardWiring::II,
     ///     StandardWiring::III,
     ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
-    /// );
-    ///
-    /// // Sets the left rotor to 'F', middle to 'C' and right to 'B'
-    /// enigma.set_position_str("FCB");
+    /// ).unwrap();
     ///
-    /// // Encode "test" starting at "FCB"
-    /// let pos_fcb = enigma.get_for_str("test", false, true).unwrap();
-    ///
-    /// // Sets all rotors to 'A'
-    /// enigma.set_position_str("AAA");
-    ///
-    /// // Encode "test" starting at "AAA"
-    /// let pos_aaa = enigma.get_for_str("test", false, true).unwrap();
+    /// let (indicator, body) = enigma.encode_message("JKL", "XYZ", "test").unwrap();
     ///
-    /// assert_ne!(pos_fcb, pos_aaa);
+    /// assert_eq!(
+    ///     ("XYZ".to_owned(), "TEST".to_owned()),
+    ///     enigma.decode_message("JKL", &indicator, &body).unwrap()
+    /// );
     /// ```
-    pub fn set_position_str(&mut self, position: &str) -> EnigmaResult<()> {
-        if position.len() != 3 {
-            return Err(crate::EnigmaError::InvalidPosition(position.to_owned()));
+    pub fn encode_message(
+        &mut self,
+        ground_setting: &str,
+        message_key: &str,
+        plaintext: &str,
+    ) -> EnigmaResult<(String, String)> {
+        if message_key.chars().count() != self.rotor_count() {
+            return Err(EnigmaError::InvalidPositionLength(
+                message_key.to_owned(),
+                self.rotor_count(),
+            ));
         }
 
-        let mut chars = position.chars();
+        self.set_position_str(ground_setting)?;
+        let indicator = self.encode_with_options(
+            message_key,
+            EncodeOptions {
+                unsupported: UnsupportedPolicy::Error,
+                ..EncodeOptions::default()
+            },
+        )?;
 
-        self.set_position(chars.next(), chars.next(), chars.next())
-    }
-
-    /// Returns the positions of the rotors as a three-long array. Index 0 is the left rotor, index 1 is the middle rotor and index 2 is the right rotor.
-    pub fn get_position(&self) -> [char; 3] {
-        [
-            char::from(self.rotor_l.get_position()),
-            char::from(self.rotor_m.get_position()),
-            char::from(self.rotor_r.get_position()),
-        ]
-    }
+        self.set_position_str(message_key)?;
+        let body = self.get_for_str(plaintext, false, false)?;
 
-    /// Returns the position of the rotors as a three-long string. First character is the left rotor, second is the middle rotor and the third is the right rotor.
-    pub fn get_position_str(&self) -> String {
-        let pos = self.get_position();
-        format!("{}{}{}", pos[0], pos[1], pos[2])
+        Ok((indicator, body))
     }
 
-    /// Rotates the rotors by one step
-    fn turn_rotors(&mut self) {
-        let notch_r = self.rotor_r.has_notch();
-        let notch_m = self.rotor_m.has_notch();
+    /// Decrypts a message produced by `encode_message()`, returning the recovered message key and the plaintext.
+    ///
+    /// The machine is left in the same state as the sender's: starting at the message key, with the rotors where the
+    /// last letter of the body left them. Characters outside of the alphabet in the body are skipped, so the
+    /// ciphertext may be grouped.
+    ///
+    /// # Errors
+    /// * `EnigmaError::InvalidPositionLength` - The ground setting or the indicator doesn't have one letter per rotor
+    /// * `EnigmaError::UnsupportedCharacter` - The indicator contains a character outside of the alphabet
+    ///
+    /// # Arguments
+    /// * `ground_setting` - Ground setting the message key was encrypted at
+    /// * `enciphered_key` - Indicator, i.e. the encrypted message key
+    /// * `ciphertext` - Message body to decrypt
+    pub fn decode_message(
+        &mut self,
+        ground_setting: &str,
+        enciphered_key: &str,
+        ciphertext: &str,
+    ) -> EnigmaResult<(String, String)> {
+        let message_key = self.recover_message_key(ground_setting, enciphered_key)?;
+        let plaintext = self.get_for_str(ciphertext, false, false)?;
 
-        self.rotor_r.rotate();
-        if notch_r {
-            self.rotor_m.rotate();
-        }
-        if notch_m {
-            self.rotor_m.rotate();
-            self.rotor_l.rotate();
-        }
+        Ok((message_key, plaintext))
     }
 
-    /// Runs a single character through the machine
+    /// Encodes a string using this enigma machine with the provided options.
     ///
     /// # Arguments
     ///
-    /// * `c` - Character to encode
-    pub fn get_for_char(&mut self, c: char) -> EnigmaResult<char> {
-        self._internal_get_for_char(c).map(char::from)
+    /// * `str` - String to encrypt
+    /// * `opts` - Options controlling the output
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, EncodeOptions, NewlinePolicy, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    ///
+    /// let opts = EncodeOptions {
+    ///     newlines: NewlinePolicy::Preserve,
+    ///     ..EncodeOptions::new(false, true)
+    /// };
+    /// let enc = enigma.encode_with_options("te,\nst", opts).unwrap();
+    ///
+    /// assert_eq!("ol\nkr", enc);
+    /// ```
+    pub fn encode_with_options(&mut self, str: &str, opts: EncodeOptions) -> EnigmaResult<String> {
+        let mut out = String::with_capacity(str.len());
+        self._internal_encode_into(str, &mut out, opts, 0, |_| {})?;
+
+        Ok(out)
     }
 
-    /// Actually runs a single character through a machine, only difference being that this method returns an ```EnigmaCharacter```.
+    /// Encodes a string with the rotors and reflector only, skipping both passes through the plugboard.
+    ///
+    /// This is a diagnostic for isolating the effect of the plugboard, not something a real machine can do. The rotors
+    /// step exactly as they would in `encode_with_options()`, and the plugboard is kept for later calls.
     ///
     /// # Arguments
     ///
-    /// * `c` - Character to encode
-    fn _internal_get_for_char(&mut self, c: char) -> EnigmaResult<EnigmaChar> {
-        let mut c = {
-            let x = EnigmaChar::try_from(c);
-            if let Err(EnigmaError::InvalidChar(c)) = x {
-                return Err(EnigmaError::UnsupportedCharacter(c));
-            }
+    /// * `str` - String to encrypt
+    /// * `opts` - Options controlling the output
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, EncodeOptions, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    ///
+    /// let plain = enigma.encode_without_plugboard("test", EncodeOptions::new(false, true)).unwrap();
+    /// enigma.set_position_str("AAA").unwrap();
+    /// let plugged = enigma.get_for_str("test", false, true).unwrap();
+    ///
+    /// assert_eq!("olpf", plain);
+    /// assert_eq!("olkr", plugged);
+    /// ```
+    pub fn encode_without_plugboard(
+        &mut self,
+        str: &str,
+        opts: EncodeOptions,
+    ) -> EnigmaResult<String> {
+        let steckerbrett = core::mem::take(&mut self.steckerbrett);
+        let uhr = self.uhr.take();
+        let out = self.encode_with_options(str, opts);
+        self.steckerbrett = steckerbrett;
+        self.uhr = uhr;
 
-            x?
-        };
+        out
+    }
 
-        self.turn_rotors();
+    #[cfg(feature = "std")]
+    /// Encodes text read from `reader` line by line, yielding one encoded string per input line.
+    ///
+    /// Line terminators are not part of the yielded strings. The machine is continuous: the rotors keep their state
+    /// from one line to the next and are never reset, so lines have to be decoded in the same order they were encoded.
+    /// Reading errors are returned as `EnigmaError::Io`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Source of the text
+    /// * `opts` - Options controlling the output
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, EncodeOptions, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    ///
+    /// let lines: Vec<String> = enigma
+    ///     .encode_lines("te\nst".as_bytes(), EncodeOptions::new(false, true))
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(vec!["ol", "kr"], lines);
+    /// ```
+    pub fn encode_lines<'a, R: std::io::BufRead + 'a>(
+        &'a mut self,
+        reader: R,
+        opts: EncodeOptions,
+    ) -> impl Iterator<Item = EnigmaResult<String>> + 'a {
+        reader.lines().map(move |line| {
+            let line = line.map_err(EnigmaError::Io)?;
+            self.encode_with_options(&line, opts)
+        })
+    }
 
-        self.steckerbrett.get(&mut c);
-        self.rotor_r.get_for(&mut c, false)?;
-        self.rotor_m.get_for(&mut c, false)?;
-        self.rotor_l.get_for(&mut c, false)?;
+    #[cfg(feature = "std")]
+    /// Encodes everything read from `input` and writes the result to `output`, a chunk at a time.
+    ///
+    /// Only a small buffer is held in memory, so arbitrarily large inputs can be encoded. ASCII letters are encoded and
+    /// every other byte is treated as an unsupported character: it is copied to the output unchanged if
+    /// `preserve_unsupported` is set, and dropped otherwise. Multibyte UTF-8 sequences are therefore passed through
+    /// whole, and for valid UTF-8 input the output is byte-for-byte the same as `get_for_str()` would produce.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Source of the text
+    /// * `output` - Destination of the encoded text
+    /// * `preserve_unsupported` - Whether non-alphabet characters should be preserved in the output
+    /// * `preserve_case` - Whether output characters should match the case of the input characters
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    ///
+    /// let mut enigma = Enigma::standard(
+    ///     StandardWiring::UKW_B,
+    ///     StandardWiring::I,
+    ///     StandardWiring::II,
+    ///     StandardWiring::III,
+    ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
+    /// ).unwrap();
+    ///
+    /// let mut out = Vec::new();
+    /// enigma.encode_stream("test".as_bytes(), &mut out, false, true).unwrap();
+    ///
+    /// assert_eq!(b"olkr", out.as_slice());
+    /// ```
+    pub fn encode_stream<R: std::io::Read, W: std::io::Write>(
+        &mut self,
+        mut input: R,
+        mut output: W,
+        preserve_unsupported: bool,
+        preserve_case: bool,
+    ) -> std::io::Result<()> {
+        let mut buf = [0u8; 8192];
 
-        self.ukw.get_for(&mut c, false)?;
+        loop {
+            let n = match input.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
 
-        self.rotor_l.get_for(&mut c, true)?;
-        self.rotor_m.get_for(&mut c, true)?;
-        self.rotor_r.get_for(&mut c, true)?;
-        self.steckerbrett.get(&mut c);
+            // Bytes are never rejected, only dropped or preserved
+            let out = self
+                .get_for_bytes(&buf[..n], preserve_unsupported, preserve_case)
+                .unwrap();
+            output.write_all(&out)?;
+        }
 
-        Ok(c)
+        output.flush()
     }
 
-    /// Encodes a string using this enigma machine.
+    /// Encodes a string using this enigma machine, reporting progress along the way.
     ///
     /// # Arguments
     ///
     /// * `str` - String to encrypt
-    /// * `preserve_unsupported` - Whether non-alphabet characters should be preserved in the output
-    /// * `preserve_case` - Whether output characters should match the case of the input characters
+    /// * `opts` - Options controlling the output
+    /// * `every` - Number of input characters between two calls of `f`. If 0, `f` is never called.
+    /// * `f` - Callback receiving the number of input characters processed so far
     ///
     /// # Examples
     ///
     /// ```
-    /// use enigma::{Enigma, steckerbrett, wiring::StandardWiring};
+    /// use enigma::{Enigma, EncodeOptions, steckerbrett, wiring::StandardWiring};
     ///
-    /// // Creates an enigma machine
     /// let mut enigma = Enigma::standard(
     ///     StandardWiring::UKW_B,
     ///     StandardWiring::I,
     ///     StandardWiring::II,
     ///     StandardWiring::III,
     ///     steckerbrett!('A' => 'Q', 'F' => 'R', 'S' => 'M').unwrap(),
-    /// );
+    /// ).unwrap();
     ///
-    /// /// Encode "test"
-    /// let enc = enigma.get_for_str("test", false, true).unwrap();
+    /// let mut reported = Vec::new();
+    /// let enc = enigma
+    ///     .encode_with_progress("test", EncodeOptions::new(false, true), 2, |n| reported.push(n))
+    ///     .unwrap();
     ///
     /// assert_eq!("olkr", enc);
+    /// assert_eq!(vec![2, 4], reported);
     /// ```
-    pub fn get_for_str(
+    pub fn encode_with_progress(
         &mut self,
         str: &str,
-        preserve_unsupported: bool,
-        preserve_case: bool,
+        opts: EncodeOptions,
+        every: usize,
+        f: impl FnMut(usize),
     ) -> EnigmaResult<String> {
-        let mut out = String::new();
+        let mut out = String::with_capacity(str.len());
+        self._internal_encode_into(str, &mut out, opts, every, f)?;
 
-        for c in str.chars() {
-            match self._internal_get_for_char(c) {
-                Ok(mut c) => {
-                    if !preserve_case {
-                        c.uppercase = true;
-                    }
+        Ok(out)
+    }
 
-                    out.push(char::from(c))
-                }
-                Err(crate::EnigmaError::UnsupportedCharacter(c)) => {
-                    if preserve_unsupported {
-                        out.push(c);
-                    }
+    /// Encodes a string and appends it to `out`, calling `f` with the number of processed input characters every
+    /// `every` characters.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to encrypt
+    /// * `out` - String to append to
+    /// * `opts` - Options controlling the output
+    /// * `every` - Number of input characters between two calls of `f`. If 0, `f` is never called.
+    /// * `f` - Progress callback
+    fn _internal_encode_into(
+        &mut self,
+        str: &str,
+        out: &mut String,
+        opts: EncodeOptions,
+        every: usize,
+        mut f: impl FnMut(usize),
+    ) -> EnigmaResult<()> {
+        // Only the right rotor moves on most keypresses, so the rest of the signal path is looked up from a cached table
+        let mut fast = FastEncoder::new(self);
+
+        for (i, c) in str.chars().enumerate() {
+            match (opts.newlines, c) {
+                (NewlinePolicy::Preserve, '\n' | '\r') => out.push(c),
+                (NewlinePolicy::Replace(sep), '\n') => out.push(sep),
+                (NewlinePolicy::Replace(_), '\r') => {}
+                _ => Self::_internal_push_char(&mut fast, out, c, opts)?,
+            }
+
+            if every != 0 && (i + 1) % every == 0 {
+                f(i + 1);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Encodes a single character and appends it to `out` according to the options
+    ///
+    /// # Arguments
+    ///
+    /// * `fast` - Encoder driving the machine
+    /// * `out` - String to append to
+    /// * `c` - Character to encode
+    /// * `opts` - Options controlling the output
+    fn _internal_push_char(
+        fast: &mut FastEncoder,
+        out: &mut String,
+        c: char,
+        opts: EncodeOptions,
+    ) -> EnigmaResult<()> {
+        match fast.encode(c) {
+            Ok(mut c) => {
+                if !opts.preserve_case {
+                    c.uppercase = true;
                 }
-                Err(e) => return Err(e),
+
+                out.push(char::from(c))
             }
+            Err(crate::EnigmaError::UnsupportedCharacter(c)) => match opts.unsupported {
+                UnsupportedPolicy::Drop => {}
+                UnsupportedPolicy::Preserve => out.push(c),
+                UnsupportedPolicy::Error => return Err(EnigmaError::UnsupportedCharacter(c)),
+            },
+            Err(e) => return Err(e),
         }
 
-        Ok(out)
+        Ok(())
+    }
+}
+
+/// Encrypts the same text from several start positions at once, spreading the work over all available cores.
+///
+/// Each position gets its own copy of the template, so the template itself is left untouched. Unsupported characters
+/// are dropped and the output is uppercase, as with `get_for_str(plaintext, false, false)`. The results are in the
+/// order of `positions`; if any position is invalid, the error of the first one is returned.
+///
+/// # Arguments
+///
+/// * `template` - Machine to copy, with everything but the position already set up
+/// * `positions` - Start positions, in the format of `set_position_str()`
+/// * `plaintext` - String to encrypt
+///
+/// # Examples
+///
+/// ```
+/// use enigma::{batch_encode, Enigma};
+///
+/// let template = Enigma::from_settings("B I II III AAA AAA AQ FR SM").unwrap();
+/// let enc = batch_encode(&template, &["AAA", "ADU"], "test").unwrap();
+///
+/// assert_eq!(vec!["OLKR", "YBYR"], enc);
+/// ```
+#[cfg(feature = "std")]
+pub fn batch_encode(
+    template: &Enigma,
+    positions: &[&str],
+    plaintext: &str,
+) -> EnigmaResult<Vec<String>> {
+    let encode = |position: &&str| {
+        let mut enigma = template.clone();
+        enigma.set_position_str(position)?;
+        enigma.get_for_str(plaintext, false, false)
+    };
+
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = positions.len().div_ceil(threads).max(1);
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = positions
+            .chunks(chunk)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(encode).collect::<Vec<_>>()))
+            .collect();
+
+        // A panic in one of the threads is a bug, pass it on
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    })
+}
+
+/// Checks that standard wirings can be installed together: the reflector, if given, has to be a reflector, the first
+/// `greek_slots` rotors Greek ones and the other rotors different ones out of I to VIII. Returns
+/// `EnigmaError::InvalidRotorSelection` with the text from `selection` otherwise.
+///
+/// # Arguments
+///
+/// * `ukw` - Reflector, or `None` if it isn't changed
+/// * `rotors` - Rotors, left to right
+/// * `greek_slots` - Number of Greek rotors on the left, 1 for an M4
+/// * `selection` - Description of the selection for the error
+fn check_selection(
+    ukw: Option<StandardWiring>,
+    rotors: &[StandardWiring],
+    greek_slots: usize,
+    selection: impl FnOnce() -> String,
+) -> EnigmaResult<()> {
+    let invalid = ukw.is_some_and(|w| !w.is_reflector())
+        || rotors.iter().enumerate().any(|(i, r)| {
+            r.is_reflector() || r.is_greek() != (i < greek_slots) || rotors[..i].contains(r)
+        });
+
+    match invalid {
+        true => Err(EnigmaError::InvalidRotorSelection(selection())),
+        false => Ok(()),
+    }
+}
+
+/// Splits a string into groups of `size` characters separated by spaces. The last group may be shorter.
+///
+/// # Arguments
+///
+/// * `text` - String to split
+/// * `size` - Number of characters in a group, must not be 0
+fn group_letters(text: &str, size: usize) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / size);
+
+    for (i, c) in text.chars().enumerate() {
+        if i != 0 && i % size == 0 {
+            out.push(' ');
+        }
+        out.push(c);
     }
+
+    out
 }
 
 #[cfg(test)]
 mod test {
-    use super::*;
-
     #[test]
     fn test_stecker() {
         assert!(
@@ -495,4 +3348,148 @@ mod test {
                 == steckerbrett!('A' => 'E', 'I' => 'O', 'M' => 'L').unwrap().0,
         );
     }
+
+    #[test]
+    fn test_stecker_validation() {
+        use super::EnigmaError;
+
+        for invalid in [
+            steckerbrett!('A' => 'A'),
+            steckerbrett!("AB AC"),
+            steckerbrett!("AB CA"),
+            steckerbrett!("AB CD EF GH IJ KL MN OP QR ST UV"),
+        ] {
+            assert!(matches!(
+                invalid,
+                Err(EnigmaError::InvalidSteckerbrettString(_))
+            ));
+        }
+
+        assert!(steckerbrett!("AB CD EF GH IJ KL MN OP QR ST").is_ok());
+        assert!(matches!(
+            steckerbrett!('A' => '#'),
+            Err(EnigmaError::InvalidChar('#'))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "zeroize")]
+    fn test_zeroize() {
+        use crate::{wiring::StandardWiring, Enigma};
+        use core::mem::ManuallyDrop;
+
+        let mut stecker = ManuallyDrop::new(steckerbrett!("AQ FR SM").unwrap());
+        // SAFETY: `stecker` is not used as a plugboard after being dropped, its bytes are only read
+        unsafe { ManuallyDrop::drop(&mut stecker) };
+        assert_eq!(stecker.0, [0; 26]);
+
+        let mut enigma = ManuallyDrop::new(
+            Enigma::standard(
+                StandardWiring::UKW_B,
+                StandardWiring::I,
+                StandardWiring::II,
+                StandardWiring::III,
+                steckerbrett!("AQ FR SM").unwrap(),
+            )
+            .unwrap(),
+        );
+        enigma.set_ring_str("BUL").unwrap();
+        enigma.set_position_str("FCB").unwrap();
+
+        let stecker: *const [u8; 26] = &enigma.steckerbrett.0;
+        // SAFETY: the plugboard is dropped in place, the memory stays owned by `enigma` and is only read
+        unsafe {
+            core::ptr::drop_in_place(&mut enigma.steckerbrett);
+            assert_eq!(*stecker, [0; 26]);
+        }
+
+        // SAFETY: the rotors are dropped in place and only read afterwards
+        unsafe { core::ptr::drop_in_place(enigma.rotors.as_mut_slice()) };
+        for rotor in enigma.rotors.iter() {
+            assert_eq!(rotor.get_position().internal, 0);
+            assert_eq!(rotor.get_ring().internal, 0);
+            assert_eq!(rotor.wiring().wiring, [0; 26]);
+        }
+        // SAFETY: the rotors were already dropped, so the vector must not drop them again
+        unsafe { enigma.rotors.set_len(0) };
+    }
+
+    #[test]
+    fn test_stecker_lookup() {
+        let s = steckerbrett!('A' => 'E').unwrap();
+
+        for (c, expected) in [('A', 'E'), ('E', 'A'), ('B', 'B'), ('z', 'z')] {
+            let mut c = super::EnigmaChar::try_from(c).unwrap();
+            s.get(&mut c);
+            assert_eq!(char::from(c), expected);
+        }
+
+        assert_eq!(steckerbrett!().0, super::Steckerbrett::default().0);
+    }
+
+    #[test]
+    fn test_stecker_alphabet() {
+        assert_eq!(steckerbrett!().to_alphabet(), "ABCDEFGHIJKLMNOPQRSTUVWXYZ");
+        assert_eq!(
+            steckerbrett!("AZ by").unwrap().to_alphabet(),
+            "ZYCDEFGHIJKLMNOPQRSTUVWXBA"
+        );
+    }
+
+    #[test]
+    fn test_stecker_replace_pairs() {
+        let mut s = steckerbrett!("AE IO ML").unwrap();
+        let before = s.0;
+
+        for pairs in [
+            [('B', 'C'), ('D', 'D'), ('F', 'G')],
+            [('B', 'C'), ('D', 'b'), ('F', 'G')],
+            [('B', 'C'), ('D', '#'), ('F', 'G')],
+        ] {
+            assert!(s.replace_pairs(&pairs).is_err());
+            assert_eq!(s.0, before);
+        }
+
+        s.replace_pairs(&[('B', 'C'), ('d', 'E')]).unwrap();
+        assert_eq!(s.pairs(), vec![('B', 'C'), ('D', 'E')]);
+
+        s.replace_pairs(&[]).unwrap();
+        assert_eq!(s.0, steckerbrett!().0);
+    }
+
+    #[test]
+    fn test_stecker_rules() {
+        use super::PlugRules;
+
+        let s = steckerbrett!("AZ MN PY OQ").unwrap();
+
+        assert_eq!(
+            s.violates_rules(PlugRules::NO_CONSECUTIVE),
+            vec![('A', 'Z'), ('M', 'N')]
+        );
+        assert_eq!(
+            s.violates_rules(PlugRules {
+                wrap_around: false,
+                ..PlugRules::NO_CONSECUTIVE
+            }),
+            vec![('M', 'N')]
+        );
+        assert_eq!(
+            s.violates_rules(PlugRules {
+                no_keyboard_neighbours: true,
+                ..PlugRules::NONE
+            }),
+            vec![('M', 'N'), ('P', 'Y')]
+        );
+    }
+
+    #[test]
+    fn test_stecker_pairs() {
+        let s = steckerbrett!('M' => 'L', 'e' => 'a', 'I' => 'O').unwrap();
+
+        assert_eq!(s.pairs(), vec![('A', 'E'), ('I', 'O'), ('L', 'M')]);
+        assert_eq!(s.to_string(), "AE IO LM");
+        assert_eq!(steckerbrett!(s.to_string().as_str()).unwrap().0, s.0);
+        assert_eq!(steckerbrett!().to_string(), "");
+    }
 }
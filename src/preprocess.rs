@@ -0,0 +1,315 @@
+//! Conversions between natural text and the letters-only text that was actually enciphered.
+//!
+//! The machine only had 26 keys, so operators spelled out everything else: word breaks and full stops became "X",
+//! digits were written as German number words and umlauts as two letters. These functions apply and undo those
+//! conventions and are independent of any machine. `transliterate()` only does the part for accented letters, for
+//! text that should otherwise stay as it is.
+
+use alloc::string::String;
+
+/// German number words for the digits 0 to 9, as they were keyed in
+const DIGIT_WORDS: [&str; 10] = [
+    "NULL", "EINS", "ZWEI", "DREI", "VIER", "FUENF", "SECHS", "SIEBEN", "ACHT", "NEUN",
+];
+
+/// Latin letters outside of A–Z with their spellings for `transliterate()`: lowercase letter, expanded, folded
+const TRANSLITERATIONS: [(char, &str, &str); 36] = [
+    ('ä', "ae", "a"),
+    ('ö', "oe", "o"),
+    ('ü', "ue", "u"),
+    ('ß', "ss", "ss"),
+    ('æ', "ae", "ae"),
+    ('œ', "oe", "oe"),
+    ('à', "a", "a"),
+    ('á', "a", "a"),
+    ('â', "a", "a"),
+    ('ã', "a", "a"),
+    ('å', "a", "a"),
+    ('ç', "c", "c"),
+    ('è', "e", "e"),
+    ('é', "e", "e"),
+    ('ê', "e", "e"),
+    ('ë', "e", "e"),
+    ('ì', "i", "i"),
+    ('í', "i", "i"),
+    ('î', "i", "i"),
+    ('ï', "i", "i"),
+    ('ñ', "n", "n"),
+    ('ò', "o", "o"),
+    ('ó', "o", "o"),
+    ('ô', "o", "o"),
+    ('õ', "o", "o"),
+    ('ø', "o", "o"),
+    ('ù', "u", "u"),
+    ('ú', "u", "u"),
+    ('û', "u", "u"),
+    ('ý', "y", "y"),
+    ('ÿ', "y", "y"),
+    ('č', "c", "c"),
+    ('ł', "l", "l"),
+    ('š', "s", "s"),
+    ('ž', "z", "z"),
+    ('ř', "r", "r"),
+];
+
+/// How `transliterate_with()` spells the German umlauts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Umlauts {
+    /// "ä" becomes "ae", as German writes it without umlauts
+    Expand,
+    /// "ä" becomes "a", dropping the dots like any other accent
+    Fold,
+}
+
+/// Kind of the last character written by `substitute_outgoing()`
+#[derive(PartialEq)]
+enum Last {
+    Letter,
+    Digit,
+    Separator,
+}
+
+/// Rewrites natural text the way an operator would key it in.
+///
+/// Letters are uppercased, "Ä", "Ö", "Ü" and "ß" become "AE", "OE", "UE" and "SS", whitespace and full stops become
+/// "X" and every digit is spelled as its German number word ("EINS", "ZWEI", ...). A run of digits is separated from
+/// adjacent letters by an "X", so `substitute_incoming()` can tell it apart from the surrounding words. Any other
+/// character is left as is, to be handled by the unsupported character policy of the machine.
+///
+/// # Arguments
+/// * `text` - Natural text to convert
+///
+/// # Examples
+///
+/// ```
+/// use enigma::preprocess::substitute_outgoing;
+///
+/// assert_eq!("ANXKOMMANDANT,XBOOTXZWEIDREIX", substitute_outgoing("An Kommandant, Boot 23."));
+/// assert_eq!("GRUESSE", substitute_outgoing("Grüße"));
+/// ```
+pub fn substitute_outgoing(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last = Last::Separator;
+
+    for c in text.chars() {
+        if let Some(digit) = c.to_digit(10) {
+            if last == Last::Letter {
+                out.push('X');
+            }
+            out.push_str(DIGIT_WORDS[digit as usize]);
+            last = Last::Digit;
+        } else if c.is_whitespace() || c == '.' {
+            out.push('X');
+            last = Last::Separator;
+        } else if c.is_alphabetic() {
+            if last == Last::Digit {
+                out.push('X');
+            }
+            match c {
+                'ä' | 'Ä' => out.push_str("AE"),
+                'ö' | 'Ö' => out.push_str("OE"),
+                'ü' | 'Ü' => out.push_str("UE"),
+                'ß' => out.push_str("SS"),
+                c => out.extend(c.to_uppercase()),
+            }
+            last = Last::Letter;
+        } else {
+            out.push(c);
+        }
+    }
+
+    out
+}
+
+/// Replaces accented Latin letters with plain ASCII letters, expanding the umlauts. Same as
+/// `transliterate_with(text, Umlauts::Expand)`.
+///
+/// # Arguments
+/// * `text` - Text to convert
+///
+/// # Examples
+///
+/// ```
+/// use enigma::preprocess::transliterate;
+///
+/// assert_eq!("schoen, naiv, Koeln, Sao Paulo, AErger", transliterate("schön, naïv, Köln, São Paulo, Ärger"));
+/// ```
+pub fn transliterate(text: &str) -> String {
+    transliterate_with(text, Umlauts::Expand)
+}
+
+/// Replaces accented Latin letters with plain ASCII letters, so the machine encodes them instead of treating them as
+/// unsupported characters.
+///
+/// Accents are dropped: "à", "á", "â", "ã" and "å" become "a", "ç" and "č" become "c", "è" to "ë" become "e", "ì" to
+/// "ï" become "i", "ñ" becomes "n", "ò" to "õ" and "ø" become "o", "ù" to "û" become "u", "ý" and "ÿ" become "y",
+/// "ł" becomes "l", "ř" becomes "r", "š" becomes "s" and "ž" becomes "z". Ligatures are split, "æ" into "ae" and "œ"
+/// into "oe", and "ß" becomes "ss". The umlauts "ä", "ö" and "ü" are spelled as chosen by `umlauts`.
+///
+/// Uppercase letters are replaced with uppercase letters, e.g. "Ä" with "AE". Everything else, including letters of
+/// other scripts, is left as is, unlike `substitute_outgoing()` which also rewrites spaces and digits. Letters missing
+/// from the table, e.g. "ś" or "ő", are therefore treated as unsupported characters by the machine.
+///
+/// # Arguments
+/// * `text` - Text to convert
+/// * `umlauts` - How to spell the umlauts
+///
+/// # Examples
+///
+/// ```
+/// use enigma::preprocess::{transliterate_with, Umlauts};
+///
+/// assert_eq!("Muller, Strasse", transliterate_with("Müller, Straße", Umlauts::Fold));
+/// ```
+pub fn transliterate_with(text: &str, umlauts: Umlauts) -> String {
+    let mut out = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        match TRANSLITERATIONS.iter().find(|(l, _, _)| *l == lower) {
+            Some((_, expanded, folded)) => {
+                let spelled = match umlauts {
+                    Umlauts::Expand => expanded,
+                    Umlauts::Fold => folded,
+                };
+
+                if lower == c {
+                    out.push_str(spelled);
+                } else {
+                    out.extend(spelled.chars().map(|s| s.to_ascii_uppercase()));
+                }
+            }
+            None => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Returns the digits spelled by `word`, if it consists of German number words only
+///
+/// # Arguments
+/// * `word` - Uppercase letters to split
+fn spelled_digits(mut word: &str) -> Option<String> {
+    let mut digits = String::new();
+
+    while !word.is_empty() {
+        // No number word is a prefix of another one, so the split is unique
+        let digit = DIGIT_WORDS.iter().position(|w| word.starts_with(w))?;
+        digits.push((b'0' + digit as u8) as char);
+        word = &word[DIGIT_WORDS[digit].len()..];
+    }
+
+    Some(digits)
+}
+
+/// Reverses `substitute_outgoing()` on deciphered text, as far as that is possible.
+///
+/// Only letters are considered, case folded. Every "X" becomes a space and every word between them that consists of
+/// number words only is turned back into digits, so numbers written by `substitute_outgoing()` always come back as
+/// they were.
+///
+/// Everything else is a heuristic, since the letters don't carry enough information:
+/// * Full stops come back as spaces, and so does an "X" that was part of a word, e.g. in "TAXI"
+/// * Number words that were spelled out in the original text, e.g. "acht", come back as digits
+/// * Umlauts are not restored, "UE" may just as well be part of "NEUE"
+///
+/// # Arguments
+/// * `text` - Deciphered text to convert
+///
+/// # Examples
+///
+/// ```
+/// use enigma::preprocess::{substitute_incoming, substitute_outgoing};
+///
+/// assert_eq!("BOOT 23 AUF KURS 270 ", substitute_incoming(&substitute_outgoing("Boot 23 auf Kurs 270.")));
+/// assert_eq!("FEIND IN SICHT", substitute_incoming("feindxinxsicht"));
+/// ```
+pub fn substitute_incoming(text: &str) -> String {
+    let letters: String = text
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    let mut out = String::with_capacity(letters.len());
+    for (i, word) in letters.split('X').enumerate() {
+        if i != 0 {
+            out.push(' ');
+        }
+
+        match spelled_digits(word) {
+            Some(digits) => out.push_str(&digits),
+            None => out.push_str(word),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_substitute_outgoing() {
+        assert_eq!(substitute_outgoing(""), "");
+        assert_eq!(substitute_outgoing("Ab cd."), "ABXCDX");
+        assert_eq!(substitute_outgoing("0 9"), "NULLXNEUN");
+        assert_eq!(substitute_outgoing("U17"), "UXEINSSIEBEN");
+        assert_eq!(substitute_outgoing("17U"), "EINSSIEBENXU");
+        assert_eq!(
+            substitute_outgoing("Übermorgen ändern"),
+            "UEBERMORGENXAENDERN"
+        );
+        assert_eq!(substitute_outgoing("a,b"), "A,B");
+    }
+
+    #[test]
+    fn test_transliterate() {
+        assert_eq!(transliterate(""), "");
+        assert_eq!(transliterate("schön"), "schoen");
+        assert_eq!(transliterate_with("schön", Umlauts::Fold), "schon");
+        assert_eq!(transliterate("ÄÖÜäöüß"), "AEOEUEaeoeuess");
+        assert_eq!(transliterate_with("ÄÖÜäöüß", Umlauts::Fold), "AOUaouss");
+        assert_eq!(transliterate("Œuvre à Besançon"), "OEuvre a Besancon");
+        assert_eq!(transliterate("España, 1. Žilina"), "Espana, 1. Zilina");
+        assert_eq!(transliterate("Ωmega"), "Ωmega");
+
+        for (l, expanded, folded) in TRANSLITERATIONS {
+            assert!(l.is_lowercase());
+            assert!(expanded
+                .chars()
+                .chain(folded.chars())
+                .all(|c| c.is_ascii_lowercase()));
+        }
+    }
+
+    #[test]
+    fn test_spelled_digits() {
+        assert_eq!(spelled_digits("").as_deref(), Some(""));
+        assert_eq!(spelled_digits("FUENFNULL").as_deref(), Some("50"));
+        assert_eq!(spelled_digits("KEINS"), None);
+        assert_eq!(spelled_digits("EINSTEIN"), None);
+    }
+
+    #[test]
+    fn test_substitute_round_trip() {
+        let text = "Am 12. Mai 1940 um 0530 Uhr: Angriff auf Quadrat AN8567, Stärke 3 Divisionen.";
+        let outgoing = substitute_outgoing(text);
+
+        // Nothing is left for the machine to drop but the punctuation it cannot type anyway
+        assert!(outgoing
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c == ':' || c == ','));
+
+        let incoming = substitute_incoming(&outgoing);
+        assert_eq!(
+            incoming,
+            "AM 12  MAI 1940 UM 0530 UHR ANGRIFF AUF QUADRAT AN 8567 STAERKE 3 DIVISIONEN "
+        );
+
+        let digits = |s: &str| s.chars().filter(char::is_ascii_digit).collect::<String>();
+        assert_eq!(digits(text), digits(&incoming));
+    }
+}
@@ -4,11 +4,27 @@ use super::wiring::Wiring;
 use super::EnigmaResult;
 
 /// Struct representing a rotor inside an enigma machine
+#[derive(Clone, PartialEq)]
 pub struct Rotor {
     /// Internal wiring of the rotor
     wiring: Wiring,
     /// Current position of this rotor
     position: u8,
+    /// Ring setting (Ringstellung) of this rotor
+    ring: u8,
+}
+
+/// Clears the position, the ring setting and the wiring, which may be a secret custom one
+#[cfg(feature = "zeroize")]
+impl Drop for Rotor {
+    fn drop(&mut self) {
+        crate::wipe(&mut self.wiring.wiring);
+        crate::wipe(&mut self.wiring.reverse_wiring);
+        crate::wipe(&mut self.wiring.notches);
+        self.wiring.notches.clear();
+        crate::wipe(core::slice::from_mut(&mut self.position));
+        crate::wipe(core::slice::from_mut(&mut self.ring));
+    }
 }
 
 impl Rotor {
@@ -20,6 +36,7 @@ impl Rotor {
         Self {
             wiring,
             position: 0,
+            ring: 0,
         }
     }
 
@@ -38,10 +55,30 @@ impl Rotor {
         Ok(())
     }
 
+    /// Sets the rotor's ring setting
+    ///
+    /// # Arguments
+    /// *ring* - Target ring setting
+    pub fn set_ring(&mut self, ring: &EnigmaChar) -> EnigmaResult<()> {
+        self.ring = ring.internal;
+
+        Ok(())
+    }
+
+    /// Returns the rotor's ring setting
+    pub fn get_ring(&self) -> EnigmaChar {
+        EnigmaChar {
+            internal: self.ring,
+            uppercase: true,
+        }
+    }
+
     /// Returns true if the rotor is currently on it's turnover notch
+    ///
+    /// The notch is attached to the alphabet ring, so it is compared with the visible position. Relative to the
+    /// wiring, the ring setting shifts it along with the letters.
     pub fn has_notch(&self) -> bool {
-        matches!(self.wiring.notch_1, Some(x) if x == self.position)
-            || matches!(self.wiring.notch_2, Some(x) if x == self.position)
+        self.wiring.notches.contains(&self.position)
     }
 
     /// Returns the rotor's current position
@@ -52,13 +89,20 @@ impl Rotor {
         }
     }
 
+    /// Returns the internal wiring of the rotor
+    pub fn wiring(&self) -> &Wiring {
+        &self.wiring
+    }
+
     /// Runs an input through this rotor
     /// 
     /// # Arguments
     /// *input* - Character to encode
     /// *reversed* - Whether to use the reverse wiring for signals travelling backwards
     pub fn get_for(&self, input: &mut EnigmaChar, reversed: bool) -> EnigmaResult<()> {
-        let inchar = (input.internal + self.position) % 26;
+        // Offset of the wiring core against the contacts, the ring setting turns the core back
+        let offset = (self.position + 26 - self.ring) % 26;
+        let inchar = (input.internal + offset) % 26;
 
         let outchar = (if reversed {
             &self.wiring.reverse_wiring
@@ -66,7 +110,7 @@ impl Rotor {
             &self.wiring.wiring
         }[inchar as usize]
             + 26
-            - self.position)
+            - offset)
             % 26;
 
         input.internal = outchar;
@@ -114,4 +158,35 @@ mod test {
         assert_rotor!(rotor1, 'J', 'A', true);
         assert_rotor!(rotor1, 'd', 'z', true);
     }
+
+    #[test]
+    fn test_ring() {
+        let mut rotor1 = Rotor::new(StandardWiring::I.into());
+
+        // Position B with ring B is the same wiring offset as position A with ring A
+        rotor1
+            .set_position(&EnigmaChar::try_from('B').unwrap())
+            .unwrap();
+        rotor1.set_ring(&EnigmaChar::try_from('B').unwrap()).unwrap();
+        assert_rotor!(rotor1, 'A', 'E', false);
+        assert_rotor!(rotor1, 'E', 'A', true);
+
+        // Position A with ring B turns the core back by one
+        rotor1
+            .set_position(&EnigmaChar::try_from('A').unwrap())
+            .unwrap();
+        assert_rotor!(rotor1, 'A', 'K', false);
+        assert_rotor!(rotor1, 'K', 'A', true);
+
+        // Ring Z wraps around
+        rotor1.set_ring(&EnigmaChar::try_from('Z').unwrap()).unwrap();
+        assert_rotor!(rotor1, 'A', 'J', false);
+        assert_rotor!(rotor1, 'J', 'A', true);
+
+        // The notch stays with the visible letter
+        rotor1
+            .set_position(&EnigmaChar::try_from('Q').unwrap())
+            .unwrap();
+        assert!(rotor1.has_notch());
+    }
 }
@@ -0,0 +1,273 @@
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{EnigmaChar, EnigmaError, EnigmaResult, Steckerbrett};
+
+/// Wiring of the Uhr disc: contact `i` on the side of the a-plugs is connected to contact `DISC[i]` on the side of
+/// the b-plugs
+const DISC: [u8; 40] = [
+    26, 11, 24, 21, 2, 31, 0, 25, 30, 39, 28, 13, 22, 35, 20, 37, 6, 23, 4, 33, 34, 19, 32, 9, 18,
+    7, 16, 17, 10, 3, 8, 1, 38, 27, 36, 29, 14, 15, 12, 5,
+];
+
+/// Contact group of each b-plug on its side of the disc. Plug `n`a uses contacts `4n` and `4n + 2`, plug `n`b uses
+/// `4 * B_PLUGS[n]` and `4 * B_PLUGS[n] + 2`, the first of each being the large pin.
+const B_PLUGS: [u8; 10] = [6, 0, 7, 5, 1, 8, 4, 2, 9, 3];
+
+/// Struct representing an Enigma Uhr, a switch box that takes the place of the plugboard cables
+///
+/// The Uhr is connected to the plugboard with 10 cables, each with an a-plug and a b-plug on its ends, and scrambles
+/// them with a disc that can be turned to 40 positions. At position 00 it is identical to 10 ordinary cables, at any
+/// other position the a-letters and b-letters are connected in a different order and, unless the position is a
+/// multiple of 4, no longer in pairs: the substitution on the way into the rotors is not the same as the one on the way
+/// back.
+///
+/// # Examples
+///
+/// ```
+/// use enigma::{Enigma, Uhr, steckerbrett, wiring::StandardWiring};
+///
+/// let pairs = [('A', 'B'), ('C', 'D'), ('E', 'F'), ('G', 'H'), ('I', 'J'), ('K', 'L'), ('M', 'N'), ('O', 'P'), ('Q', 'R'), ('S', 'T')];
+/// let mut enigma = Enigma::standard(
+///     StandardWiring::UKW_B,
+///     StandardWiring::I,
+///     StandardWiring::II,
+///     StandardWiring::III,
+///     steckerbrett!(),
+/// ).unwrap();
+/// enigma.set_uhr(Some(Uhr::new(&pairs, 27).unwrap()));
+///
+/// let enc = enigma.get_for_str("test", false, true).unwrap();
+/// enigma.reset();
+///
+/// assert_eq!("test", enigma.get_for_str(&enc, false, true).unwrap());
+/// ```
+#[derive(Clone, PartialEq, Hash)]
+pub struct Uhr {
+    /// Plugged letters, the first of each pair in the a-plug and the second in the b-plug of the same cable
+    pairs: [(u8, u8); 10],
+    /// Position of the disc, 0 to 39
+    dial: u8,
+    /// Substitution on the way from the keyboard into the rotors
+    forward: [u8; 26],
+    /// Substitution on the way from the rotors to the lamps
+    reverse: [u8; 26],
+}
+
+impl Uhr {
+    /// Number of positions of the disc
+    pub const POSITIONS: u8 = 40;
+
+    /// Creates a new Uhr connected to the provided letters
+    ///
+    /// # Errors
+    /// * `EnigmaError::InvalidSteckerbrettString` - There are not exactly 10 pairs, or a letter is used twice
+    /// * `EnigmaError::InvalidUhrDial` - The dial position is 40 or more
+    ///
+    /// # Arguments
+    /// * `pairs` - Letters to plug, the first of each pair in cable 1 to 10's a-plug and the second in its b-plug
+    /// * `dial` - Position of the disc, 0 to 39
+    pub fn new(pairs: &[(char, char)], dial: u8) -> EnigmaResult<Self> {
+        let stecker = Steckerbrett::try_from(pairs)?;
+        if pairs.len() != 10 {
+            return Err(EnigmaError::InvalidSteckerbrettString(format!(
+                "{}",
+                stecker
+            )));
+        }
+
+        let mut z = Self {
+            pairs: [(0, 0); 10],
+            dial: 0,
+            forward: [0; 26],
+            reverse: [0; 26],
+        };
+        for (plug, (a, b)) in z.pairs.iter_mut().zip(pairs) {
+            *plug = (
+                EnigmaChar::try_from(a.to_ascii_uppercase())?.internal,
+                EnigmaChar::try_from(b.to_ascii_uppercase())?.internal,
+            );
+        }
+
+        z.set_dial(dial)?;
+        Ok(z)
+    }
+
+    /// Turns the disc to a new position
+    ///
+    /// # Arguments
+    /// * `dial` - Position of the disc, 0 to 39
+    pub fn set_dial(&mut self, dial: u8) -> EnigmaResult<()> {
+        if dial >= Self::POSITIONS {
+            return Err(EnigmaError::InvalidUhrDial(dial));
+        }
+
+        self.dial = dial;
+
+        // Letters without a cable are not connected to the Uhr
+        for (i, v) in self.forward.iter_mut().enumerate() {
+            *v = i as u8;
+        }
+
+        // Every signal enters the disc on the large pin of a plug and leaves it on the small pin of a plug on the
+        // opposite side, so an a-letter is always connected to a b-letter and the other way around
+        let turned = |contact: u8| ((contact + dial) % Self::POSITIONS) as usize;
+        let group = |contact: u8| (contact + Self::POSITIONS - dial) % Self::POSITIONS / 4;
+        let back = |contact: usize| DISC.iter().position(|c| *c as usize == contact).unwrap() as u8;
+
+        for (n, (a, b)) in self.pairs.iter().enumerate() {
+            let to_b = group(DISC[turned(4 * n as u8)]);
+            let to_a = group(back(turned(4 * B_PLUGS[n])));
+
+            let m = B_PLUGS.iter().position(|p| *p == to_b).unwrap();
+            self.forward[*a as usize] = self.pairs[m].1;
+            self.forward[*b as usize] = self.pairs[to_a as usize].0;
+        }
+
+        for (i, v) in self.forward.iter().enumerate() {
+            self.reverse[*v as usize] = i as u8;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the position of the disc
+    pub fn dial(&self) -> u8 {
+        self.dial
+    }
+
+    /// Returns the plugged letters, in the order of the cables, the a-plug first
+    pub fn pairs(&self) -> Vec<(char, char)> {
+        let letter = |internal: u8| {
+            char::from(EnigmaChar {
+                internal,
+                uppercase: true,
+            })
+        };
+
+        self.pairs
+            .iter()
+            .map(|(a, b)| (letter(*a), letter(*b)))
+            .collect()
+    }
+
+    /// Runs a character through the Uhr
+    ///
+    /// # Arguments
+    /// * `char` - Character to run through
+    /// * `reversed` - Whether the signal is travelling back from the rotors to the lamps
+    pub fn get(&self, char: &mut EnigmaChar, reversed: bool) {
+        char.internal = if reversed {
+            &self.reverse
+        } else {
+            &self.forward
+        }[char.internal as usize];
+    }
+}
+
+impl core::fmt::Debug for Uhr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let pairs: String = self
+            .pairs()
+            .iter()
+            .map(|(a, b)| format!(" {}{}", a, b))
+            .collect();
+
+        write!(f, "Uhr({:02}:{})", self.dial, pairs)
+    }
+}
+
+/// The substitutions are derived from the plugged letters, so they are cleared with them
+#[cfg(feature = "zeroize")]
+impl Drop for Uhr {
+    fn drop(&mut self) {
+        for (a, b) in self.pairs.iter_mut() {
+            crate::wipe(core::slice::from_mut(a));
+            crate::wipe(core::slice::from_mut(b));
+        }
+        crate::wipe(core::slice::from_mut(&mut self.dial));
+        crate::wipe(&mut self.forward);
+        crate::wipe(&mut self.reverse);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PAIRS: [(char, char); 10] = [
+        ('A', 'B'),
+        ('C', 'D'),
+        ('E', 'F'),
+        ('G', 'H'),
+        ('I', 'J'),
+        ('K', 'L'),
+        ('M', 'N'),
+        ('O', 'P'),
+        ('Q', 'R'),
+        ('S', 'T'),
+    ];
+
+    #[test]
+    fn test_uhr_zero() {
+        let uhr = Uhr::new(&PAIRS, 0).unwrap();
+        let stecker = Steckerbrett::try_from(&PAIRS[..]).unwrap();
+
+        assert_eq!(uhr.forward, stecker.0);
+        assert_eq!(uhr.reverse, stecker.0);
+        assert_eq!(uhr.pairs(), PAIRS.to_vec());
+    }
+
+    #[test]
+    fn test_uhr_dial() {
+        let mut uhr = Uhr::new(&PAIRS, 0).unwrap();
+
+        for dial in 0..Uhr::POSITIONS {
+            uhr.set_dial(dial).unwrap();
+            assert_eq!(uhr.dial(), dial);
+
+            for i in 0..26u8 {
+                assert_eq!(uhr.reverse[uhr.forward[i as usize] as usize], i);
+
+                // a-letters are always connected to b-letters and the other way around
+                let to = uhr.forward[i as usize];
+                match PAIRS
+                    .iter()
+                    .position(|(a, b)| *a as u8 - b'A' == i || *b as u8 - b'A' == i)
+                {
+                    Some(n) if PAIRS[n].0 as u8 - b'A' == i => {
+                        assert!(PAIRS.iter().any(|(_, b)| *b as u8 - b'A' == to))
+                    }
+                    Some(_) => assert!(PAIRS.iter().any(|(a, _)| *a as u8 - b'A' == to)),
+                    None => assert_eq!(to, i),
+                }
+            }
+
+            // Only every fourth position connects the letters in pairs
+            assert_eq!(uhr.forward == uhr.reverse, dial % 4 == 0, "dial {}", dial);
+        }
+
+        uhr.set_dial(1).unwrap();
+        let mut c = EnigmaChar::try_from('A').unwrap();
+        uhr.get(&mut c, false);
+        assert_eq!(char::from(&c), 'P');
+        uhr.get(&mut c, true);
+        assert_eq!(char::from(&c), 'A');
+        assert!(matches!(
+            uhr.set_dial(40),
+            Err(EnigmaError::InvalidUhrDial(40))
+        ));
+        assert_eq!(uhr.dial(), 1);
+    }
+
+    #[test]
+    fn test_uhr_invalid() {
+        assert!(Uhr::new(&PAIRS[..9], 0).is_err());
+        assert!(Uhr::new(&[&PAIRS[..], &[('U', 'V')]].concat(), 0).is_err());
+        assert!(Uhr::new(&[&PAIRS[..9], &[('A', 'Z')]].concat(), 0).is_err());
+        assert!(Uhr::new(&[&PAIRS[..9], &[('Z', 'Z')]].concat(), 0).is_err());
+        assert!(matches!(
+            Uhr::new(&PAIRS, 40),
+            Err(EnigmaError::InvalidUhrDial(40))
+        ));
+    }
+}
@@ -1,4 +1,8 @@
-use lazy_static::lazy_static;
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
 
 use crate::{EnigmaChar, EnigmaError, EnigmaResult};
 
@@ -8,33 +12,39 @@ pub struct Wiring {
     pub wiring: [u8; 26],
     /// Reverse wiring, for decoding
     pub reverse_wiring: [u8; 26],
-    /// Optional turnover position
-    pub notch_1: Option<u8>,
-    /// Optional turnover position
-    pub notch_2: Option<u8>,
+    /// Turnover positions, in alphabetical order without repetitions
+    pub notches: Vec<u8>,
 }
 
 impl Wiring {
     /// Returns a wiring created from a provided template
     ///
+    /// Rotors can have any number of notches, from none on the Greek rotors of the M4 to several on some commercial
+    /// machines. They may be given in any order and case, repetitions are ignored.
+    ///
     /// # Arguments
     ///
     /// * `template` – Array of 26 characters of the alphabet where each letter corresponds to the letter of alphabet at the same index
-    /// * `notch_1` – Optional turnover position
-    /// * `notch_2` – Optional turnover position
-    pub fn new(
-        template: [char; 26],
-        notch_1: Option<char>,
-        notch_2: Option<char>,
-    ) -> EnigmaResult<Self> {
-        let notch_1 = notch_1
-            .map(EnigmaChar::try_from)
-            .transpose()?
-            .map(|x| x.internal);
-        let notch_2 = notch_2
-            .map(EnigmaChar::try_from)
-            .transpose()?
-            .map(|x| x.internal);
+    /// * `notches` – Turnover positions
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::wiring::{StandardWiring, Wiring};
+    ///
+    /// let standard = Wiring::from(StandardWiring::VI);
+    /// let custom = Wiring::new(standard.as_template(), &['z', 'M']).unwrap();
+    ///
+    /// assert!(custom == standard);
+    /// assert_eq!(custom.notches, vec![12, 25]);
+    /// ```
+    pub fn new(template: [char; 26], notches: &[char]) -> EnigmaResult<Self> {
+        let mut notches = notches
+            .iter()
+            .map(|c| EnigmaChar::try_from(c).map(|x| x.internal))
+            .collect::<EnigmaResult<Vec<u8>>>()?;
+        notches.sort_unstable();
+        notches.dedup();
 
         let mut wiring = [0u8; 26];
         let mut reverse_wiring = [0u8; 26];
@@ -57,10 +67,150 @@ impl Wiring {
         Ok(Self {
             wiring,
             reverse_wiring,
-            notch_1,
-            notch_2,
+            notches,
         })
     }
+
+    /// Returns a wiring created from a provided template with at most two notches, like all standard rotors have
+    ///
+    /// # Arguments
+    ///
+    /// * `template` – Array of 26 characters of the alphabet where each letter corresponds to the letter of alphabet at the same index
+    /// * `notch_1` – Optional turnover position
+    /// * `notch_2` – Optional turnover position
+    pub fn with_optional_notches(
+        template: [char; 26],
+        notch_1: Option<char>,
+        notch_2: Option<char>,
+    ) -> EnigmaResult<Self> {
+        let notches: Vec<char> = [notch_1, notch_2].into_iter().flatten().collect();
+
+        Self::new(template, &notches)
+    }
+
+    /// Creates a new reflector wiring, checked with `validate_involution()`. Reflectors have no notches.
+    ///
+    /// # Arguments
+    /// * `template` - Array of characters, where each index corresponds to the character that letter is wired to
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::wiring::Wiring;
+    ///
+    /// let mut template: Vec<char> = "BADCFEHGJILKNMPORQTSVUXWZY".chars().collect();
+    /// assert!(Wiring::new_reflector(template.clone().try_into().unwrap()).is_ok());
+    ///
+    /// // 'A' and 'B' are swapped, but 'C' is wired to 'A'
+    /// template[2] = 'A';
+    /// assert!(Wiring::new_reflector(template.try_into().unwrap()).is_err());
+    /// ```
+    pub fn new_reflector(template: [char; 26]) -> EnigmaResult<Self> {
+        let wiring = Self::new(template, &[])?;
+        wiring.validate_involution()?;
+
+        Ok(wiring)
+    }
+
+    /// Creates the wiring of a UKW-D, the reflector that could be rewired in the field.
+    ///
+    /// The UKW-D has one fixed connection, between J and Y in the letters used at Bletchley Park, and is plugged with
+    /// twelve cables connecting the other 24 letters in pairs. Returns `EnigmaError::InvalidReflector` if there are not
+    /// exactly twelve pairs, or a pair uses J or Y, connects a letter to itself or repeats a letter of another pair.
+    ///
+    /// # Arguments
+    /// * `pairs` - Letters connected by the twelve cables, case insensitive
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::wiring::Wiring;
+    ///
+    /// let pairs = [('A', 'C'), ('B', 'Z'), ('D', 'H'), ('E', 'M'), ('F', 'T'), ('G', 'O'), ('I', 'U'), ('K', 'X'), ('L', 'R'), ('N', 'P'), ('Q', 'V'), ('S', 'W')];
+    /// let ukw_d = Wiring::reflector_from_pairs(&pairs).unwrap();
+    ///
+    /// assert_eq!("CZAHMTODUYXREPGNVLWFIQSKJB", ukw_d.to_string());
+    /// ```
+    pub fn reflector_from_pairs(pairs: &[(char, char)]) -> EnigmaResult<Self> {
+        let invalid = || {
+            EnigmaError::InvalidReflector(
+                pairs
+                    .iter()
+                    .map(|(a, b)| [*a, *b].iter().collect::<String>())
+                    .collect::<Vec<String>>()
+                    .join(" "),
+            )
+        };
+
+        if pairs.len() != 12 {
+            return Err(invalid());
+        }
+
+        let mut template = ['\0'; 26];
+        template[b'J' as usize - b'A' as usize] = 'Y';
+        template[b'Y' as usize - b'A' as usize] = 'J';
+
+        for (a, b) in pairs {
+            let a = EnigmaChar::try_from(a)?;
+            let b = EnigmaChar::try_from(b)?;
+
+            let (i, o) = (usize::from(a.internal), usize::from(b.internal));
+            if i == o || template[i] != '\0' || template[o] != '\0' {
+                return Err(invalid());
+            }
+
+            template[i] = char::from(&b).to_ascii_uppercase();
+            template[o] = char::from(&a).to_ascii_uppercase();
+        }
+
+        Self::new_reflector(template)
+    }
+
+    /// Checks that the wiring can be used as a reflector: every letter has to be wired to a different letter that is
+    /// wired back to it. Returns `EnigmaError::InvalidReflector` otherwise.
+    pub fn validate_involution(&self) -> EnigmaResult<()> {
+        let valid = self
+            .wiring
+            .iter()
+            .enumerate()
+            .all(|(i, o)| *o as usize != i && self.wiring[*o as usize] as usize == i);
+
+        match valid {
+            true => Ok(()),
+            false => Err(EnigmaError::InvalidReflector(self.to_string())),
+        }
+    }
+
+    /// Returns the wiring as uppercase letters, in the format of `Wiring::new()`'s template
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use enigma::wiring::{StandardWiring, Wiring};
+    ///
+    /// let wiring = Wiring::from(StandardWiring::I);
+    /// let copy = Wiring::new(wiring.as_template(), &['Q']).unwrap();
+    ///
+    /// assert!(copy == wiring);
+    /// assert_eq!("EKMFLGDQVZNTOWYHXUSPAIBRCJ", wiring.to_string());
+    /// ```
+    pub fn as_template(&self) -> [char; 26] {
+        self.wiring.map(|internal| {
+            char::from(EnigmaChar {
+                internal,
+                uppercase: true,
+            })
+        })
+    }
+}
+
+/// Formats the wiring as its 26-letter template, without the notches
+impl core::fmt::Display for Wiring {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.as_template()
+            .iter()
+            .try_for_each(|c| write!(f, "{}", c))
+    }
 }
 
 impl Clone for Wiring {
@@ -68,116 +218,30 @@ impl Clone for Wiring {
         Self {
             wiring: self.wiring,
             reverse_wiring: self.reverse_wiring,
-            notch_1: self.notch_1,
-            notch_2: self.notch_2,
+            notches: self.notches.clone(),
         }
     }
 }
 
-lazy_static! {
-    static ref I: Wiring = Wiring::new(
-        [
-            'E', 'K', 'M', 'F', 'L', 'G', 'D', 'Q', 'V', 'Z', 'N', 'T', 'O', 'W', 'Y', 'H', 'X',
-            'U', 'S', 'P', 'A', 'I', 'B', 'R', 'C', 'J',
-        ],
-        Some('Q'),
-        None
-    )
-    .unwrap();
-    static ref II: Wiring = Wiring::new(
-        [
-            'A', 'J', 'D', 'K', 'S', 'I', 'R', 'U', 'X', 'B', 'L', 'H', 'W', 'T', 'M', 'C', 'Q',
-            'G', 'Z', 'N', 'P', 'Y', 'F', 'V', 'O', 'E',
-        ],
-        Some('E'),
-        None
-    )
-    .unwrap();
-    static ref III: Wiring = Wiring::new(
-        [
-            'B', 'D', 'F', 'H', 'J', 'L', 'C', 'P', 'R', 'T', 'X', 'V', 'Z', 'N', 'Y', 'E', 'I',
-            'W', 'G', 'A', 'K', 'M', 'U', 'S', 'Q', 'O',
-        ],
-        Some('V'),
-        None
-    )
-    .unwrap();
-    static ref IV: Wiring = Wiring::new(
-        [
-            'E', 'S', 'O', 'V', 'P', 'Z', 'J', 'A', 'Y', 'Q', 'U', 'I', 'R', 'H', 'X', 'L', 'N',
-            'F', 'T', 'G', 'K', 'D', 'C', 'M', 'W', 'B',
-        ],
-        Some('J'),
-        None
-    )
-    .unwrap();
-    static ref V: Wiring = Wiring::new(
-        [
-            'V', 'Z', 'B', 'R', 'G', 'I', 'T', 'Y', 'U', 'P', 'S', 'D', 'N', 'H', 'L', 'X', 'A',
-            'W', 'M', 'J', 'Q', 'O', 'F', 'E', 'C', 'K',
-        ],
-        Some('Z'),
-        None
-    )
-    .unwrap();
-    static ref VI: Wiring = Wiring::new(
-        [
-            'J', 'P', 'G', 'V', 'O', 'U', 'M', 'F', 'Y', 'Q', 'B', 'E', 'N', 'H', 'Z', 'R', 'D',
-            'K', 'A', 'S', 'X', 'L', 'I', 'C', 'T', 'W',
-        ],
-        Some('Z'),
-        Some('M')
-    )
-    .unwrap();
-    static ref VII: Wiring = Wiring::new(
-        [
-            'N', 'Z', 'J', 'H', 'G', 'R', 'C', 'X', 'M', 'Y', 'S', 'W', 'B', 'O', 'U', 'F', 'A',
-            'I', 'V', 'L', 'P', 'E', 'K', 'Q', 'D', 'T',
-        ],
-        Some('Z'),
-        Some('M')
-    )
-    .unwrap();
-    static ref VIII: Wiring = Wiring::new(
-        [
-            'F', 'K', 'Q', 'H', 'T', 'L', 'X', 'O', 'C', 'B', 'J', 'S', 'P', 'D', 'Z', 'R', 'A',
-            'M', 'E', 'W', 'N', 'I', 'U', 'Y', 'G', 'V',
-        ],
-        Some('Z'),
-        Some('M')
-    )
-    .unwrap();
-    static ref UKW_A: Wiring = Wiring::new(
-        [
-            'E', 'J', 'M', 'Z', 'A', 'L', 'Y', 'X', 'V', 'B', 'W', 'F', 'C', 'R', 'Q', 'U', 'O',
-            'N', 'T', 'S', 'P', 'I', 'K', 'H', 'G', 'D',
-        ],
-        None,
-        None
-    )
-    .unwrap();
-    static ref UKW_B: Wiring = Wiring::new(
-        [
-            'Y', 'R', 'U', 'H', 'Q', 'S', 'L', 'D', 'P', 'X', 'N', 'G', 'O', 'K', 'M', 'I', 'E',
-            'B', 'F', 'Z', 'C', 'W', 'V', 'J', 'A', 'T',
-        ],
-        None,
-        None
-    )
-    .unwrap();
-    static ref UKW_C: Wiring = Wiring::new(
-        [
-            'F', 'V', 'P', 'J', 'I', 'A', 'O', 'Y', 'E', 'D', 'R', 'Z', 'X', 'W', 'G', 'C', 'T',
-            'K', 'U', 'Q', 'S', 'B', 'N', 'M', 'H', 'L',
-        ],
-        None,
-        None
-    )
-    .unwrap();
+impl PartialEq for Wiring {
+    fn eq(&self, other: &Self) -> bool {
+        self.wiring == other.wiring
+            && self.reverse_wiring == other.reverse_wiring
+            && self.notches == other.notches
+    }
 }
 
-/// Enum holding standard wirings for the Enigma M3 machine
+impl core::hash::Hash for Wiring {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        // The reverse wiring is derived from the wiring, so it doesn't need to be hashed
+        self.wiring.hash(state);
+        self.notches.hash(state);
+    }
+}
+
+/// Enum holding standard wirings for the Enigma M3 and M4 machines
 #[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StandardWiring {
     I,
     II,
@@ -187,9 +251,97 @@ pub enum StandardWiring {
     VI,
     VII,
     VIII,
+    /// Greek rotor of the M4
+    Beta,
+    /// Greek rotor of the M4
+    Gamma,
     UKW_A,
     UKW_B,
     UKW_C,
+    /// Thin reflector of the M4
+    UKW_B_Thin,
+    /// Thin reflector of the M4
+    UKW_C_Thin,
+}
+
+impl StandardWiring {
+    /// Returns the template and notches of this wiring, in the format of `Wiring::new()`'s arguments
+    const fn spec(self) -> (&'static str, &'static [char]) {
+        match self {
+            StandardWiring::I => ("EKMFLGDQVZNTOWYHXUSPAIBRCJ", &['Q']),
+            StandardWiring::II => ("AJDKSIRUXBLHWTMCQGZNPYFVOE", &['E']),
+            StandardWiring::III => ("BDFHJLCPRTXVZNYEIWGAKMUSQO", &['V']),
+            StandardWiring::IV => ("ESOVPZJAYQUIRHXLNFTGKDCMWB", &['J']),
+            StandardWiring::V => ("VZBRGITYUPSDNHLXAWMJQOFECK", &['Z']),
+            StandardWiring::VI => ("JPGVOUMFYQBENHZRDKASXLICTW", &['Z', 'M']),
+            StandardWiring::VII => ("NZJHGRCXMYSWBOUFAIVLPEKQDT", &['Z', 'M']),
+            StandardWiring::VIII => ("FKQHTLXOCBJSPDZRAMEWNIUYGV", &['Z', 'M']),
+            StandardWiring::Beta => ("LEYJVCNIXWPBQMDRTAKZGFUHOS", &[]),
+            StandardWiring::Gamma => ("FSOKANUERHMBTIYCWLQPZXVGJD", &[]),
+            StandardWiring::UKW_A => ("EJMZALYXVBWFCRQUONTSPIKHGD", &[]),
+            StandardWiring::UKW_B => ("YRUHQSLDPXNGOKMIEBFZCWVJAT", &[]),
+            StandardWiring::UKW_C => ("FVPJIAOYEDRZXWGCTKUQSBNMHL", &[]),
+            StandardWiring::UKW_B_Thin => ("ENKQAUYWJICOPBLMDXZVFTHRGS", &[]),
+            StandardWiring::UKW_C_Thin => ("RDOBJNTKVEHMLFCWZAXGYIPSUQ", &[]),
+        }
+    }
+
+    /// All standard wirings, rotors first and reflectors last
+    pub const ALL: [StandardWiring; 15] = [
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        StandardWiring::IV,
+        StandardWiring::V,
+        StandardWiring::VI,
+        StandardWiring::VII,
+        StandardWiring::VIII,
+        StandardWiring::Beta,
+        StandardWiring::Gamma,
+        StandardWiring::UKW_A,
+        StandardWiring::UKW_B,
+        StandardWiring::UKW_C,
+        StandardWiring::UKW_B_Thin,
+        StandardWiring::UKW_C_Thin,
+    ];
+
+    /// Returns the standard wiring identical to the provided one, including its notches, if there is one
+    ///
+    /// # Arguments
+    ///
+    /// * `wiring` - Wiring to identify
+    pub fn identify(wiring: &Wiring) -> Option<StandardWiring> {
+        // Compared against the specs directly, building every standard wiring would allocate on each lookup
+        Self::ALL.into_iter().find(|w| {
+            let (template, notches) = w.spec();
+
+            template
+                .bytes()
+                .zip(wiring.wiring)
+                .all(|(t, o)| t - b'A' == o)
+                && notches.len() == wiring.notches.len()
+                && notches
+                    .iter()
+                    .all(|n| wiring.notches.contains(&(*n as u8 - b'A')))
+        })
+    }
+
+    /// Returns true if this is the wiring of a reflector (Umkehrwalze), including the thin ones of the M4
+    pub fn is_reflector(&self) -> bool {
+        matches!(
+            self,
+            StandardWiring::UKW_A
+                | StandardWiring::UKW_B
+                | StandardWiring::UKW_C
+                | StandardWiring::UKW_B_Thin
+                | StandardWiring::UKW_C_Thin
+        )
+    }
+
+    /// Returns true if this is one of the Greek rotors of the M4, which only fit next to the thin reflector
+    pub fn is_greek(&self) -> bool {
+        matches!(self, StandardWiring::Beta | StandardWiring::Gamma)
+    }
 }
 
 impl TryFrom<&str> for StandardWiring {
@@ -204,14 +356,37 @@ impl TryFrom<&str> for StandardWiring {
             "VI" => StandardWiring::VI,
             "VII" => StandardWiring::VII,
             "VIII" => StandardWiring::VIII,
+            "Beta" => StandardWiring::Beta,
+            "Gamma" => StandardWiring::Gamma,
             "UKW_A" => StandardWiring::UKW_A,
             "UKW_B" => StandardWiring::UKW_B,
             "UKW_C" => StandardWiring::UKW_C,
+            "UKW_B_Thin" => StandardWiring::UKW_B_Thin,
+            "UKW_C_Thin" => StandardWiring::UKW_C_Thin,
             _ => return Err(()),
         })
     }
 }
 
+/// Parses the name of a standard wiring, as returned by `Display`, ignoring case. Returns
+/// `EnigmaError::InvalidWiringName` for unknown names.
+impl core::str::FromStr for StandardWiring {
+    type Err = EnigmaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        StandardWiring::ALL
+            .into_iter()
+            .find(|w| <&str>::from(*w).eq_ignore_ascii_case(s))
+            .ok_or_else(|| EnigmaError::InvalidWiringName(s.to_owned()))
+    }
+}
+
+impl core::fmt::Display for StandardWiring {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str((*self).into())
+    }
+}
+
 impl From<StandardWiring> for &str {
     fn from(w: StandardWiring) -> Self {
         match w {
@@ -223,27 +398,50 @@ impl From<StandardWiring> for &str {
             StandardWiring::VI => "VI",
             StandardWiring::VII => "VII",
             StandardWiring::VIII => "VIII",
+            StandardWiring::Beta => "Beta",
+            StandardWiring::Gamma => "Gamma",
             StandardWiring::UKW_A => "UKW_A",
             StandardWiring::UKW_B => "UKW_B",
             StandardWiring::UKW_C => "UKW_C",
+            StandardWiring::UKW_B_Thin => "UKW_B_Thin",
+            StandardWiring::UKW_C_Thin => "UKW_C_Thin",
         }
     }
 }
 
+/// Forward and reverse substitutions of the standard wirings, in the order of `StandardWiring::ALL`
+const TABLES: [([u8; 26], [u8; 26]); 15] = build_tables();
+
+/// Builds `TABLES` from the templates at compile time, so converting a standard wiring only copies them
+const fn build_tables() -> [([u8; 26], [u8; 26]); 15] {
+    let mut tables = [([0u8; 26], [0u8; 26]); 15];
+
+    let mut i = 0;
+    while i < tables.len() {
+        let template = StandardWiring::ALL[i].spec().0.as_bytes();
+        let mut j = 0;
+        while j < 26 {
+            let out = template[j] - b'A';
+            tables[i].0[j] = out;
+            tables[i].1[out as usize] = j as u8;
+            j += 1;
+        }
+        i += 1;
+    }
+
+    tables
+}
+
 impl From<StandardWiring> for Wiring {
     fn from(w: StandardWiring) -> Self {
-        match w {
-            StandardWiring::I => I.clone(),
-            StandardWiring::II => II.clone(),
-            StandardWiring::III => III.clone(),
-            StandardWiring::IV => IV.clone(),
-            StandardWiring::V => V.clone(),
-            StandardWiring::VI => VI.clone(),
-            StandardWiring::VII => VII.clone(),
-            StandardWiring::VIII => VIII.clone(),
-            StandardWiring::UKW_A => UKW_A.clone(),
-            StandardWiring::UKW_B => UKW_B.clone(),
-            StandardWiring::UKW_C => UKW_C.clone(),
+        let (wiring, reverse_wiring) = TABLES[w as usize];
+        let mut notches: Vec<u8> = w.spec().1.iter().map(|c| *c as u8 - b'A').collect();
+        notches.sort_unstable();
+
+        Wiring {
+            wiring,
+            reverse_wiring,
+            notches,
         }
     }
 }
@@ -281,4 +479,179 @@ mod test {
         assert_eq!("EKMFLGDQVZNTOWYHXUSPAIBRCJ", strw);
         assert_eq!("UWYGADFPVZBECKMTHXSLRINQOJ", strrw);
     }
+
+    #[test]
+    fn test_wiring_template() {
+        // `TABLES` is indexed by the discriminant
+        for (i, w) in StandardWiring::ALL.into_iter().enumerate() {
+            assert_eq!(w as usize, i);
+        }
+
+        for w in StandardWiring::ALL {
+            let wiring = Wiring::from(w);
+            let copy = Wiring::new(wiring.as_template(), &[]).unwrap();
+
+            assert_eq!(copy.wiring, wiring.wiring);
+            assert_eq!(copy.reverse_wiring, wiring.reverse_wiring);
+            assert_eq!(copy.to_string(), wiring.to_string());
+
+            // The tables built at compile time are the ones `Wiring::new()` builds from the template
+            let (template, notches) = w.spec();
+            let template: [char; 26] = template.chars().collect::<Vec<char>>().try_into().unwrap();
+            assert!(Wiring::new(template, notches).unwrap() == wiring);
+        }
+
+        assert_eq!(
+            Wiring::from(StandardWiring::UKW_B).to_string(),
+            "YRUHQSLDPXNGOKMIEBFZCWVJAT"
+        );
+    }
+
+    #[test]
+    fn test_wiring_involution() {
+        for w in StandardWiring::ALL.iter().filter(|w| w.is_reflector()) {
+            assert!(Wiring::from(*w).validate_involution().is_ok(), "{}", w);
+        }
+        for w in StandardWiring::ALL.iter().filter(|w| !w.is_reflector()) {
+            assert!(Wiring::from(*w).validate_involution().is_err(), "{}", w);
+        }
+
+        let mut template = Wiring::from(StandardWiring::UKW_B).as_template();
+        assert!(Wiring::new_reflector(template).is_ok());
+
+        // 'A' and 'Y' wired to themselves
+        template[0] = 'A';
+        template[24] = 'Y';
+        assert!(matches!(
+            Wiring::new_reflector(template),
+            Err(EnigmaError::InvalidReflector(_))
+        ));
+
+        // Not a permutation at all
+        template[24] = 'A';
+        assert!(matches!(
+            Wiring::new_reflector(template),
+            Err(EnigmaError::InvalidNumber(_))
+        ));
+    }
+
+    #[test]
+    fn test_wiring_m4() {
+        let to_string = |table: &[u8; 26]| -> String {
+            table
+                .iter()
+                .map(|i| {
+                    char::from(EnigmaChar {
+                        internal: *i,
+                        uppercase: true,
+                    })
+                })
+                .collect()
+        };
+
+        for (w, strw, strrw) in [
+            (
+                StandardWiring::Beta,
+                "LEYJVCNIXWPBQMDRTAKZGFUHOS",
+                "RLFOBVUXHDSANGYKMPZQWEJICT",
+            ),
+            (
+                StandardWiring::Gamma,
+                "FSOKANUERHMBTIYCWLQPZXVGJD",
+                "ELPZHAXJNYDRKFCTSIBMGWQVOU",
+            ),
+        ] {
+            let wiring: Wiring = w.into();
+
+            assert_eq!(strw, to_string(&wiring.wiring));
+            assert_eq!(strrw, to_string(&wiring.reverse_wiring));
+            assert!(wiring.notches.is_empty());
+        }
+
+        // Reflectors are their own inverse
+        for w in [StandardWiring::UKW_B_Thin, StandardWiring::UKW_C_Thin] {
+            let wiring: Wiring = w.into();
+
+            assert_eq!(wiring.wiring, wiring.reverse_wiring);
+        }
+    }
+
+    #[test]
+    fn test_wiring_parse() {
+        for w in StandardWiring::ALL {
+            assert_eq!(w.to_string().parse::<StandardWiring>().unwrap(), w);
+            assert_eq!(
+                w.to_string()
+                    .to_lowercase()
+                    .parse::<StandardWiring>()
+                    .unwrap(),
+                w
+            );
+        }
+
+        assert_eq!(
+            "ukw_b".parse::<StandardWiring>().unwrap(),
+            StandardWiring::UKW_B
+        );
+        assert!(matches!(
+            "XIV".parse::<StandardWiring>(),
+            Err(EnigmaError::InvalidWiringName(name)) if name == "XIV"
+        ));
+    }
+
+    #[test]
+    fn test_wiring_eq() {
+        let custom = Wiring::new(
+            [
+                'E', 'K', 'M', 'F', 'L', 'G', 'D', 'Q', 'V', 'Z', 'N', 'T', 'O', 'W', 'Y', 'H',
+                'X', 'U', 'S', 'P', 'A', 'I', 'B', 'R', 'C', 'J',
+            ],
+            &['Q'],
+        )
+        .unwrap();
+
+        assert!(custom == Wiring::from(StandardWiring::I));
+        assert!(custom != Wiring::from(StandardWiring::II));
+
+        let moved_notch = Wiring {
+            notches: vec![0],
+            ..custom.clone()
+        };
+        assert!(custom != moved_notch);
+    }
+
+    #[test]
+    fn test_wiring_identify() {
+        for w in StandardWiring::ALL {
+            assert_eq!(StandardWiring::identify(&w.into()), Some(w));
+        }
+
+        let wiring = Wiring {
+            notches: Vec::new(),
+            ..StandardWiring::I.into()
+        };
+        assert_eq!(StandardWiring::identify(&wiring), None);
+
+        // Notches are a set, but all of them have to match
+        let template = Wiring::from(StandardWiring::VI).as_template();
+        let both = Wiring::new(template, &['m', 'Z', 'M']).unwrap();
+        assert_eq!(StandardWiring::identify(&both), Some(StandardWiring::VI));
+        let one = Wiring::new(template, &['M']).unwrap();
+        assert_eq!(StandardWiring::identify(&one), None);
+    }
+
+    #[test]
+    fn test_wiring_kind() {
+        let reflectors: Vec<_> = StandardWiring::ALL
+            .into_iter()
+            .filter(StandardWiring::is_reflector)
+            .collect();
+        assert_eq!(reflectors, StandardWiring::ALL[10..].to_vec());
+
+        assert!(StandardWiring::Beta.is_greek());
+        assert!(StandardWiring::Gamma.is_greek());
+        assert!(!StandardWiring::I.is_greek());
+        assert!(!StandardWiring::UKW_B_Thin.is_greek());
+        assert!(!StandardWiring::VIII.is_reflector());
+    }
 }
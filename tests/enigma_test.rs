@@ -1,4 +1,9 @@
-use enigma::{steckerbrett, wiring::StandardWiring, Enigma, EnigmaError};
+use enigma::{
+    steckerbrett,
+    wiring::{StandardWiring, Wiring},
+    EncodeOptions, Enigma, EnigmaChar, EnigmaError, KeySheet, NewlinePolicy, NextStep,
+    Steckerbrett, Uhr, UnsupportedPolicy,
+};
 
 #[test]
 fn test_enigma_rotors() {
@@ -8,7 +13,8 @@ fn test_enigma_rotors() {
         StandardWiring::II,
         StandardWiring::III,
         steckerbrett!(),
-    );
+    )
+    .unwrap();
 
     enigma.set_position_str("AAT").unwrap();
     enigma.get_for_char('A').unwrap();
@@ -21,6 +27,11 @@ fn test_enigma_rotors() {
     enigma.set_position_str("AEU").unwrap();
     enigma.get_for_char('A').unwrap();
     assert_eq!(enigma.get_position_str(), "BFV");
+    assert_eq!(enigma.get_position(), ['B', 'F', 'V']);
+    assert_eq!(enigma.get_position_numeric(), [2, 6, 22]);
+
+    enigma.set_position_str("ZAZ").unwrap();
+    assert_eq!(enigma.get_position_numeric(), [26, 1, 26]);
 }
 
 #[test]
@@ -31,7 +42,8 @@ fn test_enigma_chars() {
         StandardWiring::II,
         StandardWiring::III,
         steckerbrett!(),
-    );
+    )
+    .unwrap();
 
     enigma.set_position_str("AET").unwrap();
 
@@ -65,7 +77,8 @@ fn test_enigma_str() {
         StandardWiring::II,
         StandardWiring::III,
         steckerbrett!(),
-    );
+    )
+    .unwrap();
 
     enigma.set_position_str("AET").unwrap();
 
@@ -97,7 +110,8 @@ fn test_enigma_stecker() {
         StandardWiring::II,
         StandardWiring::III,
         steckerbrett!('X' => 'Q').unwrap(),
-    );
+    )
+    .unwrap();
 
     let mut enigma_nosteck = Enigma::standard(
         StandardWiring::UKW_B,
@@ -105,7 +119,8 @@ fn test_enigma_stecker() {
         StandardWiring::II,
         StandardWiring::III,
         steckerbrett!(),
-    );
+    )
+    .unwrap();
 
     const TEST_STR: &str = "bida leonardovi";
 
@@ -132,3 +147,2641 @@ fn test_enigma_stecker() {
         enigma_nosteck.get_for_str(&enc2, true, true).unwrap()
     );
 }
+
+#[test]
+fn test_enigma_next_step() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+
+    enigma.set_position_str("AAA").unwrap();
+    assert_eq!(
+        enigma.next_step_info(),
+        NextStep {
+            left: false,
+            middle: false,
+            right: true,
+            double_step: false,
+        }
+    );
+
+    enigma.set_position_str("AAV").unwrap();
+    assert_eq!(
+        enigma.next_step_info(),
+        NextStep {
+            left: false,
+            middle: true,
+            right: true,
+            double_step: false,
+        }
+    );
+
+    enigma.set_position_str("ADV").unwrap();
+    enigma.get_for_char('A').unwrap();
+    assert_eq!(enigma.get_position_str(), "AEW");
+    assert_eq!(
+        enigma.next_step_info(),
+        NextStep {
+            left: true,
+            middle: true,
+            right: true,
+            double_step: true,
+        }
+    );
+    enigma.get_for_char('A').unwrap();
+    assert_eq!(enigma.get_position_str(), "BFX");
+}
+
+#[test]
+fn test_enigma_mapping_cycles() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+
+    enigma.set_position_str("AAB").unwrap();
+    let cycles = enigma.mapping_cycles();
+
+    assert_eq!(cycles.len(), 13 * 4);
+    assert_eq!(enigma.get_position_str(), "AAB");
+
+    // Every cycle must be a swap that the machine performs in both directions
+    for pair in cycles.split(['(', ')']).filter(|p| !p.is_empty()) {
+        let mut chars = pair.chars();
+        let (a, b) = (chars.next().unwrap(), chars.next().unwrap());
+
+        enigma.set_position_str("AAA").unwrap();
+        assert_eq!(enigma.get_for_char(a).unwrap(), b);
+        enigma.set_position_str("AAA").unwrap();
+        assert_eq!(enigma.get_for_char(b).unwrap(), a);
+    }
+}
+
+#[test]
+fn test_enigma_autokey() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+
+    enigma.set_position_str("AAA").unwrap();
+    let chain = enigma.encode_autokey('a', 6).unwrap();
+    assert_eq!(enigma.get_position_str(), "AAG");
+
+    enigma.set_position_str("AAA").unwrap();
+    let mut c = 'a';
+    for expected in chain.chars() {
+        c = enigma.get_for_char(c).unwrap();
+        assert_eq!(c, expected);
+    }
+
+    assert_eq!(enigma.encode_autokey('A', 0).unwrap(), "");
+    assert!(matches!(
+        enigma.encode_autokey('#', 3),
+        Err(EnigmaError::UnsupportedCharacter('#'))
+    ));
+}
+
+#[test]
+fn test_enigma_structurally_eq() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+
+    let same = Enigma::new(
+        StandardWiring::UKW_B.into(),
+        StandardWiring::I.into(),
+        StandardWiring::II.into(),
+        StandardWiring::III.into(),
+        steckerbrett!('A' => 'Q').unwrap(),
+    );
+
+    let swapped = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::II,
+        StandardWiring::I,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+
+    enigma.set_position_str("QWE").unwrap();
+
+    assert!(enigma.structurally_eq(&same));
+    assert!(!enigma.structurally_eq(&swapped));
+}
+
+#[test]
+fn test_enigma_progress() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+
+    let input = "Bida Leonardovi".repeat(10);
+
+    enigma.set_position_str("AET").unwrap();
+    let expected = enigma.get_for_str(&input, true, true).unwrap();
+
+    let mut reported = Vec::new();
+    enigma.set_position_str("AET").unwrap();
+    let enc = enigma
+        .encode_with_progress(&input, EncodeOptions::new(true, true), 40, |n| {
+            reported.push(n)
+        })
+        .unwrap();
+
+    assert_eq!(expected, enc);
+    assert_eq!(vec![40, 80, 120], reported);
+
+    enigma.set_position_str("AET").unwrap();
+    let enc = enigma
+        .encode_with_progress(&input, EncodeOptions::default(), 0, |_| {
+            panic!("Callback must not be called")
+        })
+        .unwrap();
+    enigma.set_position_str("AET").unwrap();
+    assert_eq!(enigma.get_for_str(&input, false, false).unwrap(), enc);
+}
+
+#[test]
+fn test_enigma_matches_vector() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+
+    assert!(enigma
+        .matches_vector("AET", "Bida Leonardovi", "AGQNQ YIEUW BXSB")
+        .unwrap());
+    assert!(!enigma
+        .matches_vector("AEU", "Bida Leonardovi", "AGQNQ YIEUW BXSB")
+        .unwrap());
+    assert!(!enigma
+        .matches_vector("AET", "Bida Leonardovi", "AGQNQ YIEUW BXS")
+        .unwrap());
+    assert!(enigma.matches_vector("AE", "Bida", "AGQN").is_err());
+}
+
+#[test]
+fn test_enigma_as_standard_config() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_C,
+        StandardWiring::V,
+        StandardWiring::VIII,
+        StandardWiring::I,
+        steckerbrett!("AQ FR").unwrap(),
+    )
+    .unwrap();
+    enigma.set_position_str("XYZ").unwrap();
+
+    let (ukw, rotors, pos, stecker) = enigma.as_standard_config().unwrap();
+    assert_eq!(ukw, StandardWiring::UKW_C);
+    assert_eq!(
+        rotors,
+        [StandardWiring::V, StandardWiring::VIII, StandardWiring::I]
+    );
+    assert_eq!(pos, "XYZ");
+    assert_eq!(stecker.to_string(), "AQ FR");
+
+    let custom = Enigma::new(
+        StandardWiring::UKW_B.into(),
+        StandardWiring::I.into(),
+        Wiring::new(
+            [
+                'B', 'A', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P',
+                'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
+            ],
+            &['A'],
+        )
+        .unwrap(),
+        StandardWiring::III.into(),
+        steckerbrett!(),
+    );
+    assert!(custom.as_standard_config().is_none());
+}
+
+#[test]
+fn test_enigma_transmission() {
+    let mut day_key = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::IV,
+        StandardWiring::II,
+        StandardWiring::V,
+        steckerbrett!("AQ FR SM").unwrap(),
+    )
+    .unwrap();
+    day_key.set_position_str("GHI").unwrap();
+
+    let transmission =
+        Enigma::prepare_transmission(&day_key, "RTZ", "Feindliche Infanterie").unwrap();
+    assert_eq!(day_key.get_position_str(), "GHI");
+
+    let mut groups = transmission.split(' ');
+    let indicator = groups.next().unwrap();
+    let groups: Vec<&str> = groups.collect();
+    assert_eq!(indicator.len(), 3);
+    assert_eq!(
+        groups.iter().map(|g| g.len()).collect::<Vec<_>>(),
+        vec![5, 5, 5, 5]
+    );
+
+    let mut check = day_key.clone();
+    assert_eq!(check.get_for_str(indicator, false, false).unwrap(), "RTZ");
+    check.set_position_str("RTZ").unwrap();
+    assert_eq!(
+        check.get_for_str(&groups.concat(), false, false).unwrap(),
+        "FEINDLICHEINFANTERIE"
+    );
+
+    assert_eq!(
+        Enigma::receive_transmission(&day_key, &transmission).unwrap(),
+        "FEINDLICHEINFANTERIE"
+    );
+    assert!(Enigma::prepare_transmission(&day_key, "RT", "Feind").is_err());
+}
+
+#[test]
+fn test_enigma_replay() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+    enigma.set_position_str("ADT").unwrap();
+
+    let mut snapshot = enigma.clone();
+    let lamps = enigma.replay("Bida, Leonardovi!").unwrap();
+
+    assert_eq!(
+        lamps,
+        snapshot
+            .get_for_str("BIDALEONARDOVI", false, false)
+            .unwrap()
+    );
+    assert_eq!(enigma.get_position_str(), snapshot.get_position_str());
+}
+
+#[test]
+fn test_enigma_position_length() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+
+    assert_eq!(enigma.rotor_count(), 3);
+
+    for position in ["", "AB", "ABCD", "ÄB"] {
+        assert!(matches!(
+            enigma.set_position_str(position),
+            Err(EnigmaError::InvalidPositionLength(p, 3)) if p == position
+        ));
+    }
+
+    assert!(matches!(
+        enigma.set_position_str("AÄB"),
+        Err(EnigmaError::InvalidChar('Ä'))
+    ));
+    assert_eq!(enigma.get_position_str(), "AAA");
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_enigma_entropy() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!("AQ FR SM").unwrap(),
+    )
+    .unwrap();
+
+    let plaintext = "Feindliche Infanterie Kolonne beobachtet ".repeat(20);
+    let ciphertext = enigma.get_for_str(&plaintext, false, false).unwrap();
+
+    assert!(
+        enigma::analysis::shannon_entropy(&ciphertext)
+            > enigma::analysis::shannon_entropy(&plaintext)
+    );
+}
+
+#[test]
+fn test_enigma_newlines() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+
+    const INPUT: &str = "Bida,\r\nLeo!\nnardovi";
+
+    enigma.set_position_str("AET").unwrap();
+    let letters = enigma.get_for_str(INPUT, false, true).unwrap();
+    assert_eq!(letters, "AgqnQyieuwbxsb");
+
+    let mut encode = |newlines, preserve_unsupported| {
+        enigma.set_position_str("AET").unwrap();
+        enigma
+            .encode_with_options(
+                INPUT,
+                EncodeOptions {
+                    newlines,
+                    ..EncodeOptions::new(preserve_unsupported, true)
+                },
+            )
+            .unwrap()
+    };
+
+    assert_eq!(encode(NewlinePolicy::Unsupported, false), "AgqnQyieuwbxsb");
+    assert_eq!(
+        encode(NewlinePolicy::Unsupported, true),
+        "Agqn,\r\nQyi!\neuwbxsb"
+    );
+    assert_eq!(
+        encode(NewlinePolicy::Preserve, false),
+        "Agqn\r\nQyi\neuwbxsb"
+    );
+    assert_eq!(
+        encode(NewlinePolicy::Preserve, true),
+        "Agqn,\r\nQyi!\neuwbxsb"
+    );
+    assert_eq!(
+        encode(NewlinePolicy::Replace(' '), false),
+        "Agqn Qyi euwbxsb"
+    );
+    assert_eq!(
+        encode(NewlinePolicy::Replace('/'), true),
+        "Agqn,/Qyi!/euwbxsb"
+    );
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_enigma_stream() {
+    let mut text = String::new();
+    while text.len() < 1 << 20 {
+        text.push_str(
+            "Grüße aus Berlin, 1942!\nThe quick brown FOX jumps over the lazy dog. š Ł 乁 ",
+        );
+    }
+
+    for (preserve_unsupported, preserve_case) in [(false, false), (true, true)] {
+        let mut enigma = Enigma::standard(
+            StandardWiring::UKW_B,
+            StandardWiring::I,
+            StandardWiring::II,
+            StandardWiring::III,
+            steckerbrett!("AQ FR SM").unwrap(),
+        )
+        .unwrap();
+        let mut streamed = Enigma::standard(
+            StandardWiring::UKW_B,
+            StandardWiring::I,
+            StandardWiring::II,
+            StandardWiring::III,
+            steckerbrett!("AQ FR SM").unwrap(),
+        )
+        .unwrap();
+
+        let expected = enigma
+            .get_for_str(&text, preserve_unsupported, preserve_case)
+            .unwrap();
+
+        let mut out = Vec::new();
+        streamed
+            .encode_stream(
+                text.as_bytes(),
+                &mut out,
+                preserve_unsupported,
+                preserve_case,
+            )
+            .unwrap();
+
+        assert_eq!(out, expected.as_bytes());
+        assert_eq!(streamed.get_position_str(), enigma.get_position_str());
+    }
+
+    // Characters outside of ASCII are never letters, whatever their low byte is
+    let mut enigma = Enigma::from_settings("B I II III AAA AAA AQ FR SM").unwrap();
+    assert_eq!(enigma.get_for_str("š Ł 乁", true, true).unwrap(), "š Ł 乁");
+    assert_eq!(enigma.get_position_str(), "AAA");
+    assert!(matches!(
+        enigma.get_for_char('Ł'),
+        Err(EnigmaError::UnsupportedCharacter('Ł'))
+    ));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_enigma_lines() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+
+    const INPUT: &str = "Bida\r\n\nLeo nardovi\n";
+
+    enigma.set_position_str("AET").unwrap();
+    let lines: Vec<String> = enigma
+        .encode_lines(INPUT.as_bytes(), EncodeOptions::new(true, true))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(lines, vec!["Agqn", "", "Qyi euwbxsb"]);
+    assert_eq!(enigma.get_position_str(), "BGH");
+
+    enigma.set_position_str("AET").unwrap();
+    let decoded: Vec<String> = enigma
+        .encode_lines(lines.join("\n").as_bytes(), EncodeOptions::new(true, true))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(decoded, vec!["Bida", "", "Leo nardovi"]);
+
+    let invalid: &[u8] = &[b'a', 0xff, b'\n'];
+    assert!(matches!(
+        enigma
+            .encode_lines(invalid, EncodeOptions::default())
+            .next(),
+        Some(Err(EnigmaError::Io(_)))
+    ));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_enigma_structure_hash() {
+    let build = |r: StandardWiring, stecker: &str| {
+        Enigma::standard(
+            StandardWiring::UKW_B,
+            StandardWiring::I,
+            StandardWiring::II,
+            r,
+            steckerbrett!(stecker).unwrap(),
+        )
+        .unwrap()
+    };
+
+    let mut a = build(StandardWiring::III, "AQ FR");
+    let b = build(StandardWiring::III, "FR AQ");
+    a.set_position_str("XYZ").unwrap();
+
+    assert_eq!(a.structure_hash(), b.structure_hash());
+    assert_ne!(
+        a.structure_hash(),
+        build(StandardWiring::IV, "AQ FR").structure_hash()
+    );
+    assert_ne!(
+        a.structure_hash(),
+        build(StandardWiring::III, "AQ").structure_hash()
+    );
+
+    let unique: std::collections::HashSet<u64> = [a, b, build(StandardWiring::IV, "AQ FR")]
+        .iter()
+        .map(Enigma::structure_hash)
+        .collect();
+    assert_eq!(unique.len(), 2);
+}
+
+#[test]
+fn test_enigma_unsupported_policy() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+
+    let mut encode = |input, unsupported| {
+        enigma.set_position_str("AAA").unwrap();
+        enigma.encode_with_options(
+            input,
+            EncodeOptions {
+                unsupported,
+                ..EncodeOptions::default()
+            },
+        )
+    };
+
+    let ab = encode("ab", UnsupportedPolicy::Drop).unwrap();
+    assert_eq!(encode("a#b", UnsupportedPolicy::Drop).unwrap(), ab);
+    assert_eq!(
+        encode("a#b", UnsupportedPolicy::Preserve).unwrap(),
+        format!("{}#{}", &ab[..1], &ab[1..])
+    );
+    assert!(matches!(
+        encode("a#b", UnsupportedPolicy::Error),
+        Err(EnigmaError::UnsupportedCharacter('#'))
+    ));
+    assert_eq!(encode("ab", UnsupportedPolicy::Error).unwrap(), ab);
+
+    enigma.set_position_str("AAA").unwrap();
+    assert!(enigma
+        .encode_with_options(
+            "a\nb",
+            EncodeOptions {
+                unsupported: UnsupportedPolicy::Error,
+                newlines: NewlinePolicy::Preserve,
+                ..EncodeOptions::default()
+            },
+        )
+        .is_ok());
+}
+
+#[test]
+fn test_enigma_recover_message_key() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_C,
+        StandardWiring::III,
+        StandardWiring::I,
+        StandardWiring::V,
+        steckerbrett!("BX KL").unwrap(),
+    )
+    .unwrap();
+
+    enigma.set_position_str("WXC").unwrap();
+    let indicator = enigma.get_for_str("BLA", false, false).unwrap();
+    enigma.set_position_str("BLA").unwrap();
+    let body = enigma.get_for_str("Bida Leonardovi", false, false).unwrap();
+
+    enigma.set_position_str("QQQ").unwrap();
+    assert_eq!(
+        enigma.recover_message_key("WXC", &indicator).unwrap(),
+        "BLA"
+    );
+    assert_eq!(enigma.get_position_str(), "BLA");
+    assert_eq!(
+        enigma.get_for_str(&body, false, false).unwrap(),
+        "BIDALEONARDOVI"
+    );
+
+    assert!(enigma.recover_message_key("WXC", "AB").is_err());
+    assert!(matches!(
+        enigma.recover_message_key("WXC", "A#B"),
+        Err(EnigmaError::UnsupportedCharacter('#'))
+    ));
+}
+
+#[test]
+fn test_enigma_message() {
+    const PLAIN: &str = "Feindliche Infanterie Kolonne beobachtet";
+
+    let mut sender = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::II,
+        StandardWiring::IV,
+        StandardWiring::V,
+        steckerbrett!("AV BS CG DL FU HZ IN KM OW RX").unwrap(),
+    )
+    .unwrap();
+    sender.set_ring_str("BUL").unwrap();
+    let mut receiver = sender.clone();
+
+    let (indicator, body) = sender.encode_message("WXC", "BLA", PLAIN).unwrap();
+    assert_eq!(indicator.len(), 3);
+    assert_eq!(body.len(), 37);
+
+    // Same as doing the procedure by hand
+    let mut check = receiver.clone();
+    check.set_position_str("WXC").unwrap();
+    assert_eq!(check.get_for_str("BLA", false, false).unwrap(), indicator);
+    check.set_position_str("BLA").unwrap();
+    assert_eq!(check.get_for_str(PLAIN, false, false).unwrap(), body);
+
+    // The machine is left after the body, starting from the message key
+    assert_eq!(sender.get_position_str(), check.get_position_str());
+    sender.reset();
+    assert_eq!(sender.get_position_str(), "BLA");
+
+    let grouped = body
+        .as_bytes()
+        .chunks(5)
+        .map(|g| std::str::from_utf8(g).unwrap())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let (key, plain) = receiver
+        .decode_message("WXC", &indicator, &grouped)
+        .unwrap();
+    assert_eq!(key, "BLA");
+    assert_eq!(plain, "FEINDLICHEINFANTERIEKOLONNEBEOBACHTET");
+    assert_eq!(receiver.get_position_str(), check.get_position_str());
+
+    assert!(matches!(
+        sender.encode_message("WXC", "BL", PLAIN),
+        Err(EnigmaError::InvalidPositionLength(_, 3))
+    ));
+    assert!(matches!(
+        sender.encode_message("WXC", "BLAB", PLAIN),
+        Err(EnigmaError::InvalidPositionLength(_, 3))
+    ));
+    assert!(matches!(
+        sender.encode_message("WXC", "B#A", PLAIN),
+        Err(EnigmaError::UnsupportedCharacter('#'))
+    ));
+    assert!(sender.encode_message("WX", "BLA", PLAIN).is_err());
+    assert!(receiver.decode_message("WXC", "AB", &body).is_err());
+}
+
+#[test]
+fn test_enigma_config() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_C,
+        StandardWiring::VIII,
+        StandardWiring::II,
+        StandardWiring::IV,
+        steckerbrett!("BX KL").unwrap(),
+    )
+    .unwrap();
+    enigma.set_position_str("MNO").unwrap();
+
+    let config = enigma.config();
+    assert_eq!(
+        config
+            .rotors
+            .iter()
+            .map(|r| r.name.unwrap())
+            .collect::<Vec<_>>(),
+        vec![StandardWiring::VIII, StandardWiring::II, StandardWiring::IV]
+    );
+    assert_eq!(config.rotors[0].notches, vec!['M', 'Z']);
+    assert!(config.reflector.notches.is_empty());
+    assert_eq!(config.positions, "MNO");
+    assert_eq!(config.plugboard, vec![('B', 'X'), ('K', 'L')]);
+
+    let snapshot = config.clone();
+    enigma.get_for_char('A').unwrap();
+    assert_ne!(enigma.config(), snapshot);
+    assert_eq!(config, snapshot);
+}
+
+#[test]
+fn test_enigma_from_config() {
+    let mut enigma = Enigma::new(
+        Wiring::from(StandardWiring::UKW_C),
+        Wiring::with_optional_notches(
+            [
+                'Q', 'W', 'E', 'R', 'T', 'Z', 'U', 'I', 'O', 'A', 'S', 'D', 'F', 'G', 'H', 'J',
+                'K', 'P', 'Y', 'X', 'C', 'V', 'B', 'N', 'M', 'L',
+            ],
+            Some('E'),
+            Some('R'),
+        )
+        .unwrap(),
+        StandardWiring::II.into(),
+        StandardWiring::IV.into(),
+        steckerbrett!("BX KL").unwrap(),
+    );
+    enigma.set_ring_str("CAT").unwrap();
+    enigma.set_position_str("MNO").unwrap();
+
+    let config = enigma.to_config();
+    assert_eq!(config.rotors[0].name, None);
+
+    let mut copy = Enigma::from_config(&config).unwrap();
+    assert_eq!(copy.to_config(), config);
+    assert_eq!(
+        enigma.get_for_str("round trip", false, true).unwrap(),
+        copy.get_for_str("round trip", false, true).unwrap()
+    );
+
+    let mut m4 = Enigma::m4(
+        StandardWiring::UKW_C_Thin,
+        StandardWiring::Gamma,
+        StandardWiring::V,
+        StandardWiring::VI,
+        StandardWiring::VII,
+        steckerbrett!(),
+    )
+    .unwrap();
+    m4.set_position_str("QRST").unwrap();
+    let config4 = m4.to_config();
+    assert_eq!(config4.fixed, 1);
+    let mut copy = Enigma::from_config(&config4).unwrap();
+    assert_eq!(copy.rotor_count(), 4);
+    assert_eq!(
+        m4.get_for_str("round trip", false, true).unwrap(),
+        copy.get_for_str("round trip", false, true).unwrap()
+    );
+
+    // Four rotors that all step, unlike an M4, and five rotors
+    for rotors in [
+        vec![
+            StandardWiring::V,
+            StandardWiring::I,
+            StandardWiring::II,
+            StandardWiring::III,
+        ],
+        vec![
+            StandardWiring::V,
+            StandardWiring::I,
+            StandardWiring::II,
+            StandardWiring::III,
+            StandardWiring::IV,
+        ],
+    ] {
+        let mut wide = Enigma::with_rotors(
+            StandardWiring::UKW_B.into(),
+            rotors.into_iter().map(Wiring::from).collect(),
+            steckerbrett!("AQ").unwrap(),
+        )
+        .unwrap();
+        let config = wide.to_config();
+        assert_eq!(config.fixed, 0);
+
+        let mut copy = Enigma::from_config(&config).unwrap();
+        assert!(copy == wide);
+        // Long enough for the leftmost rotor to step
+        let plain = "A".repeat(26 * 26 * 2);
+        assert_eq!(
+            wide.get_for_str(&plain, false, true).unwrap(),
+            copy.get_for_str(&plain, false, true).unwrap()
+        );
+    }
+
+    let mut bad = config4;
+    bad.fixed = 2;
+    assert!(matches!(
+        Enigma::from_config(&bad),
+        Err(EnigmaError::InvalidRotorSelection(_))
+    ));
+
+    let mut bad = config.clone();
+    bad.plugboard = vec![('A', 'B'), ('B', 'C')];
+    assert!(matches!(
+        Enigma::from_config(&bad),
+        Err(EnigmaError::InvalidSteckerbrettString(_))
+    ));
+
+    let mut bad = config.clone();
+    bad.rotors.pop();
+    assert!(matches!(
+        Enigma::from_config(&bad),
+        Err(EnigmaError::InvalidRotorSelection(_))
+    ));
+
+    let mut bad = config;
+    bad.positions = "MN".to_owned();
+    assert!(matches!(
+        Enigma::from_config(&bad),
+        Err(EnigmaError::InvalidPositionLength(_, 3))
+    ));
+}
+
+#[test]
+fn test_enigma_rewind() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+
+    assert!(matches!(
+        enigma.rewind(1),
+        Err(EnigmaError::InvalidRewind(1))
+    ));
+    enigma.rewind(0).unwrap();
+
+    enigma.set_position_str("ADS").unwrap();
+    let mut positions = vec![enigma.get_position_str()];
+    for _ in 0..30 {
+        enigma.get_for_char('A').unwrap();
+        positions.push(enigma.get_position_str());
+    }
+
+    enigma.rewind(10).unwrap();
+    assert_eq!(enigma.get_position_str(), positions[20]);
+    enigma.rewind(15).unwrap();
+    assert_eq!(enigma.get_position_str(), positions[5]);
+
+    assert!(enigma.rewind(6).is_err());
+    assert_eq!(enigma.get_position_str(), positions[5]);
+
+    // Rewinding and encoding again reproduces the same ciphertext
+    let enc = enigma.get_for_str("Bida Leonardovi", true, true).unwrap();
+    enigma.rewind(14).unwrap();
+    assert_eq!(
+        enigma.get_for_str("Bida Leonardovi", true, true).unwrap(),
+        enc
+    );
+
+    enigma.rewind(19).unwrap();
+    assert_eq!(enigma.get_position_str(), "ADS");
+}
+
+#[test]
+fn test_enigma_without_plugboard() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!('X' => 'Q').unwrap(),
+    )
+    .unwrap();
+
+    const TEST_STR: &str = "bida leonardovi";
+
+    enigma.set_position_str("AET").unwrap();
+    let enc = enigma
+        .encode_without_plugboard(TEST_STR, EncodeOptions::new(true, true))
+        .unwrap();
+    assert_eq!("agqn qyieuwbxsb", enc);
+    assert_eq!(enigma.get_position_str(), "BGH");
+
+    enigma.set_position_str("AET").unwrap();
+    assert_eq!(
+        "agxn xyieuwbqsb",
+        enigma.get_for_str(TEST_STR, true, true).unwrap()
+    );
+}
+
+#[test]
+fn test_enigma_self_encoding_positions() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!("AQ FR").unwrap(),
+    )
+    .unwrap();
+    enigma.set_position_str("QWE").unwrap();
+
+    assert!(enigma.self_encoding_positions().is_empty());
+    assert_eq!(enigma.get_position_str(), "QWE");
+
+    // A reflector that sends 'A' back unchanged and swaps the remaining letters in pairs
+    let broken = Enigma::new(
+        Wiring::new(
+            [
+                'A', 'C', 'B', 'E', 'D', 'G', 'F', 'I', 'H', 'K', 'J', 'M', 'L', 'O', 'N', 'Q',
+                'P', 'S', 'R', 'U', 'T', 'W', 'V', 'Y', 'X', 'Z',
+            ],
+            &[],
+        )
+        .unwrap(),
+        StandardWiring::I.into(),
+        StandardWiring::II.into(),
+        StandardWiring::III.into(),
+        steckerbrett!(),
+    );
+
+    let positions = broken.self_encoding_positions();
+    assert_eq!(positions.len(), 26);
+    assert_eq!(positions[0], 'A');
+}
+
+#[test]
+fn test_enigma_set_rotors_by_name() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!("AQ").unwrap(),
+    )
+    .unwrap();
+    let expected = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::VIII,
+        StandardWiring::I,
+        StandardWiring::V,
+        steckerbrett!("AQ").unwrap(),
+    )
+    .unwrap();
+
+    enigma.set_position_str("QWE").unwrap();
+    enigma.set_ring_str("RTZ").unwrap();
+    enigma.set_rotors_by_name(" VIII I\tV ").unwrap();
+    assert_eq!(enigma.get_position_str(), "AAA");
+    assert_eq!(enigma.get_ring_str(), "AAA");
+    assert!(enigma.structurally_eq(&expected));
+    #[cfg(feature = "std")]
+    assert_eq!(enigma.structure_hash(), expected.structure_hash());
+
+    for names in ["I I II", "II III", "I II III IV", "UKW_B I II"] {
+        assert!(matches!(
+            enigma.set_rotors_by_name(names),
+            Err(EnigmaError::InvalidRotorSelection(n)) if n == names
+        ));
+    }
+    assert!(matches!(
+        enigma.set_rotors_by_name("I II IX"),
+        Err(EnigmaError::InvalidWiringName(n)) if n == "IX"
+    ));
+    assert!(enigma.structurally_eq(&expected));
+}
+
+#[test]
+fn test_enigma_ring() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!("AQ FR SM").unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(enigma.get_ring_str(), "AAA");
+    enigma.set_ring_str("BUL").unwrap();
+    assert_eq!(enigma.get_ring(), ['B', 'U', 'L']);
+
+    const PLAIN: &str =
+        "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOGANDKEEPSRUNNINGPASTTHEOLDMILLUNTILSUNSET";
+    const CIPHER: &str =
+        "QIOTCQIWUBFSTBVZQVRYLFGBQDNSGDBEKYRYAYCKFJXGZOQDMTWXLEYUVMGCYHMIFWXHYFSAISQ";
+
+    enigma.set_position_str("AAA").unwrap();
+    assert_eq!(enigma.get_for_str(PLAIN, false, false).unwrap(), CIPHER);
+    enigma.set_position_str("AAA").unwrap();
+    assert_eq!(enigma.get_for_str(CIPHER, false, false).unwrap(), PLAIN);
+
+    // Ring settings don't change where the rotors turn over
+    enigma.set_ring_str("ZZZ").unwrap();
+    enigma.set_position_str("AAV").unwrap();
+    enigma.get_for_char('A').unwrap();
+    assert_eq!(enigma.get_position_str(), "ABW");
+
+    assert!(matches!(
+        enigma.set_ring_str("AB"),
+        Err(EnigmaError::InvalidPositionLength(_, 3))
+    ));
+    assert!(matches!(
+        enigma.set_ring_str("A#B"),
+        Err(EnigmaError::InvalidChar('#'))
+    ));
+    assert_eq!(enigma.get_ring_str(), "ZZZ");
+}
+
+#[test]
+fn test_enigma_1930_manual() {
+    // Example message from the 1930 Enigma instruction manual
+    const PLAIN: &str = "FEINDLIQEINFANTERIEKOLONNEBEOBAQTETXANFANGSUEDAUSGANGBAERWALDEXENDEDREIKMOSTWAERTSNEUSTADT";
+    const CIPHER: &str = "GCDSE AHUGW TQGRK VLFGX UCALX VYMIG MMNMF DXTGN VHVRM MEVOU YFZSL RHDRR XFJWC FHUHM UNZEF RDISI KBGPM YVXUZ";
+
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_A,
+        StandardWiring::II,
+        StandardWiring::I,
+        StandardWiring::III,
+        steckerbrett!("AM FI NV PS TU WZ").unwrap(),
+    )
+    .unwrap();
+    enigma.set_ring_str("XMV").unwrap();
+
+    assert!(enigma.matches_vector("ABL", PLAIN, CIPHER).unwrap());
+    assert!(enigma.matches_vector("ABL", CIPHER, PLAIN).unwrap());
+}
+
+#[test]
+fn test_enigma_m4() {
+    // Message from U-264 (Kapitänleutnant Hartwig Looks), 1942
+    const CIPHER: &str = "NCZWVUSXPNYMINHZXMQXSFWXWLKJAHSHNMCOCCAKUQPMKCSMHKSEINJUSBLKIOSXCKUBHMLLXCSJUSRRDVKOHULXWCCBGVLIYXEOAHXRHKKFVDREWEZLXOBAFGYUJQUKGRTVUKAMEURBVEKSUHHVOYHABCJWMAKLFKLMYFVNRIZRVVRTKOFDANJMOLBGFFLEOPRGTFLVRHOWOPBEKVWMUQFMPWPARMFHAGKXIIBG";
+    const PLAIN: &str = "VONVONJLOOKSJHFFTTTEINSEINSDREIZWOYYQNNSNEUNINHALTXXBEIANGRIFFUNTERWASSERGEDRUECKTYWABOSXLETZTERGEGNERSTANDNULACHTDREINULUHRMARQUANTONJOTANEUNACHTSEYHSDREIYZWOZWONULGRADYACHTSMYSTOSSENACHXEKNSVIERMBFAELLTYNNNNNNOOOVIERYSICHTEINSNULL";
+
+    let mut enigma = Enigma::m4(
+        StandardWiring::UKW_B_Thin,
+        StandardWiring::Beta,
+        StandardWiring::II,
+        StandardWiring::IV,
+        StandardWiring::I,
+        steckerbrett!("AT BL DF GJ HM NW OP QY RZ VX").unwrap(),
+    )
+    .unwrap();
+    enigma.set_ring_str("AAAV").unwrap();
+    assert_eq!(enigma.get_ring_str(), "AAAV");
+
+    assert!(enigma.matches_vector("VJNA", CIPHER, PLAIN).unwrap());
+    assert!(enigma.matches_vector("VJNA", PLAIN, CIPHER).unwrap());
+
+    // The Greek rotor never steps
+    assert_eq!(&enigma.get_position_str()[..1], "V");
+
+    assert!(matches!(
+        enigma.set_position_str("AAA"),
+        Err(EnigmaError::InvalidPositionLength(_, 4))
+    ));
+    assert!(enigma.as_standard_config().is_none());
+    assert_eq!(enigma.config().rotors.len(), 4);
+
+    // Thin reflector B with Beta at 'A' is equivalent to the M3 reflector B
+    let mut m4 = Enigma::m4(
+        StandardWiring::UKW_B_Thin,
+        StandardWiring::Beta,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+    let mut m3 = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+    m4.set_position_str("AFCB").unwrap();
+    m3.set_position_str("FCB").unwrap();
+    assert_eq!(
+        m4.get_for_str(PLAIN, false, false).unwrap(),
+        m3.get_for_str(PLAIN, false, false).unwrap()
+    );
+    assert!(!m4.structurally_eq(&m3));
+
+    m4.set_rotors_by_name("Gamma IV V VI").unwrap();
+    assert_eq!(m4.get_position_str(), "AAAA");
+    assert!(m4.set_rotors_by_name("IV V VI").is_err());
+    assert!(m4.set_rotors_by_name("IV Gamma V VI").is_err());
+    assert!(m3.set_rotors_by_name("Beta V VI").is_err());
+
+    let mut fast = m4.clone();
+    m4.set_position_str("BCDE").unwrap();
+    fast.set_position_str("BCDE").unwrap();
+    let expected = m4.get_for_str(PLAIN, false, false).unwrap();
+    let mut encoder = fast.prepare_fast();
+    let actual: String = PLAIN
+        .chars()
+        .map(|c| encoder.encode_char(c).unwrap())
+        .collect();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_enigma_clone() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!("AQ FR SM").unwrap(),
+    )
+    .unwrap();
+    enigma.set_position_str("FCB").unwrap();
+
+    let mut clone = enigma.clone();
+    assert!(clone == enigma);
+
+    clone.get_for_str("test", false, false).unwrap();
+    assert_eq!(enigma.get_position_str(), "FCB");
+    assert!(clone != enigma);
+
+    clone.set_position_str("FCB").unwrap();
+    assert!(clone == enigma);
+
+    clone.set_ring_str("AAB").unwrap();
+    assert!(clone != enigma);
+}
+
+#[test]
+fn test_enigma_plug() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+    let mut reference = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!("AQ FR SM").unwrap(),
+    )
+    .unwrap();
+
+    enigma.plug('A', 'Q').unwrap();
+    enigma.plug('r', 'f').unwrap();
+    enigma.plug('S', 'M').unwrap();
+    enigma.plug('M', 'S').unwrap();
+    assert!(enigma == reference);
+    assert!(enigma.config().plugboard == reference.config().plugboard);
+
+    // Plugging over an existing cable
+    for (a, b) in [('A', 'F'), ('B', 'Q'), ('C', 'C')] {
+        assert!(matches!(
+            enigma.plug(a, b),
+            Err(EnigmaError::InvalidSteckerbrettString(_))
+        ));
+    }
+    assert!(matches!(
+        enigma.plug('A', '#'),
+        Err(EnigmaError::InvalidChar('#'))
+    ));
+    assert!(enigma == reference);
+
+    // Unplugging a letter without a cable
+    enigma.unplug('B').unwrap();
+    assert!(enigma == reference);
+
+    enigma.unplug('m').unwrap();
+    reference.unplug('S').unwrap();
+    assert!(enigma == reference);
+    assert_eq!(
+        enigma.get_for_str("test", false, true).unwrap(),
+        reference.get_for_str("test", false, true).unwrap()
+    );
+
+    let mut stecker = steckerbrett!("AQ").unwrap();
+    assert!(stecker.contains('q'));
+    assert!(!stecker.contains('B'));
+    assert!(!stecker.contains('#'));
+
+    stecker.replace_pairs(&[]).unwrap();
+    assert!(!stecker.contains('A'));
+}
+
+#[test]
+fn test_enigma_grouped() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!("AQ FR SM").unwrap(),
+    )
+    .unwrap();
+
+    let plain = enigma.get_for_str("Hello, world!", false, false).unwrap();
+    enigma.set_position_str("AAA").unwrap();
+    let grouped = enigma.get_for_str_grouped("Hello, world!", 5).unwrap();
+    assert_eq!(grouped.len(), "XXXXX XXXXX".len());
+    assert_eq!(grouped.replace(' ', ""), plain);
+
+    enigma.set_position_str("AAA").unwrap();
+    let grouped = enigma.get_for_str_grouped("attack at dawn", 5).unwrap();
+    assert_eq!(
+        grouped.split(' ').map(str::len).collect::<Vec<_>>(),
+        vec![5, 5, 2]
+    );
+    assert!(grouped.chars().all(|c| c == ' ' || c.is_ascii_uppercase()));
+
+    enigma.set_position_str("AAA").unwrap();
+    assert_eq!(
+        enigma.get_for_str_grouped("attack at dawn", 0).unwrap(),
+        grouped.replace(' ', "")
+    );
+}
+
+#[test]
+fn test_enigma_with_rotors() {
+    const PLAIN: &str =
+        "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOGANDKEEPSRUNNINGPASTTHEOLDMILLUNTILSUNSET";
+
+    let mut standard = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!("AQ FR SM").unwrap(),
+    )
+    .unwrap();
+    let mut bank = Enigma::with_rotors(
+        StandardWiring::UKW_B.into(),
+        vec![
+            StandardWiring::I.into(),
+            StandardWiring::II.into(),
+            StandardWiring::III.into(),
+        ],
+        steckerbrett!("AQ FR SM").unwrap(),
+    )
+    .unwrap();
+    assert!(bank == standard);
+
+    for pos in ["AAA", "ADU", "QEV"] {
+        standard.set_position_str(pos).unwrap();
+        bank.set_position_str(pos).unwrap();
+        assert_eq!(
+            bank.get_for_str(PLAIN, false, false).unwrap(),
+            standard.get_for_str(PLAIN, false, false).unwrap()
+        );
+        assert_eq!(bank.get_position_str(), standard.get_position_str());
+    }
+
+    // A fourth rotor is carried by the left rotor's notch
+    let mut bank = Enigma::with_rotors(
+        StandardWiring::UKW_B.into(),
+        vec![
+            StandardWiring::IV.into(),
+            StandardWiring::I.into(),
+            StandardWiring::II.into(),
+            StandardWiring::III.into(),
+        ],
+        steckerbrett!(),
+    )
+    .unwrap();
+    bank.set_position_str("AQAV").unwrap();
+    bank.get_for_char('A').unwrap();
+    assert_eq!(bank.get_position_str(), "BRBW");
+    assert_eq!(bank.get_position(), ['R', 'B', 'W']);
+    assert!(matches!(
+        bank.set_position_str("AAA"),
+        Err(EnigmaError::InvalidPositionLength(_, 4))
+    ));
+
+    let mut copy = bank.clone();
+    let expected = bank.get_for_str(PLAIN, false, false).unwrap();
+    let mut fast = copy.prepare_fast();
+    let actual: String = PLAIN
+        .chars()
+        .map(|c| fast.encode_char(c).unwrap())
+        .collect();
+    assert_eq!(actual, expected);
+
+    assert!(matches!(
+        Enigma::with_rotors(
+            StandardWiring::UKW_B.into(),
+            vec![StandardWiring::I.into(), StandardWiring::II.into()],
+            steckerbrett!(),
+        ),
+        Err(EnigmaError::InvalidRotorSelection(_))
+    ));
+}
+
+#[test]
+fn test_enigma_current_permutation() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!("AQ FR SM").unwrap(),
+    )
+    .unwrap();
+
+    for pos in ["AAA", "ADU", "AEV", "QEV", "ZZZ"] {
+        enigma.set_position_str(pos).unwrap();
+        let mut stepped = enigma.clone();
+        stepped.get_for_char('A').unwrap();
+        let permutation = stepped.current_permutation();
+
+        for (i, p) in permutation.iter().enumerate() {
+            let mut machine = enigma.clone();
+            let c = machine.get_for_char((b'A' + i as u8) as char).unwrap();
+
+            assert_eq!((b'A' + p) as char, c);
+            assert_eq!(permutation[*p as usize], i as u8);
+        }
+    }
+
+    // The cached string path and the straightforward per-character path agree
+    let input = "Kolonne beobachtet, Anfang Sued! ".repeat(200);
+    let mut slow = enigma.clone();
+    let expected: String = input
+        .chars()
+        .map(|c| slow.get_for_char(c).unwrap_or(c))
+        .collect();
+
+    assert_eq!(enigma.get_for_str(&input, true, true).unwrap(), expected);
+    assert!(enigma == slow);
+}
+
+#[test]
+fn test_enigma_random() {
+    fn generator(seed: u64) -> impl FnMut(usize) -> usize {
+        let mut state = seed;
+        move |n| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize % n
+        }
+    }
+
+    let configs: Vec<_> = (1..=8)
+        .map(|seed| Enigma::random(generator(seed)).config())
+        .collect();
+
+    for config in &configs {
+        let rotors: Vec<StandardWiring> = config.rotors.iter().map(|r| r.name.unwrap()).collect();
+        assert_eq!(rotors.len(), 3);
+        assert!(rotors.iter().all(|r| StandardWiring::ALL[..5].contains(r)));
+        assert!(rotors[0] != rotors[1] && rotors[1] != rotors[2] && rotors[0] != rotors[2]);
+        assert!(matches!(
+            config.reflector.name,
+            Some(StandardWiring::UKW_A | StandardWiring::UKW_B | StandardWiring::UKW_C)
+        ));
+
+        let mut letters: Vec<char> = config
+            .plugboard
+            .iter()
+            .flat_map(|(a, b)| [*a, *b])
+            .collect();
+        letters.sort_unstable();
+        letters.dedup();
+        assert_eq!(letters.len(), 20);
+
+        // The configuration passes the same validation as a manually built one
+        assert!(Enigma::from_config(config).is_ok());
+    }
+
+    assert_eq!(Enigma::random(generator(1)).config(), configs[0]);
+    assert!(configs[1..].iter().any(|c| *c != configs[0]));
+}
+
+#[test]
+fn test_enigma_reset() {
+    const PLAIN: &str = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG";
+
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!("AQ FR SM").unwrap(),
+    )
+    .unwrap();
+
+    // A new machine starts at "AAA"
+    enigma.get_for_str(PLAIN, false, false).unwrap();
+    enigma.reset();
+    assert_eq!(enigma.get_position_str(), "AAA");
+
+    enigma.set_ring_str("BUL").unwrap();
+    enigma.set_position_str("QEV").unwrap();
+    let cipher = enigma.get_for_str(PLAIN, false, false).unwrap();
+    assert_ne!(enigma.get_position_str(), "QEV");
+
+    enigma.reset();
+    assert_eq!(enigma.get_position_str(), "QEV");
+    assert_eq!(enigma.get_ring_str(), "BUL");
+    assert_eq!(enigma.get_for_str(&cipher, false, false).unwrap(), PLAIN);
+
+    // Nothing to rewind after a reset
+    enigma.reset();
+    assert!(enigma.rewind(1).is_err());
+}
+
+#[test]
+fn test_enigma_uhr() {
+    const PLAIN: &str = "FEINDLICHEINFANTERIEKOLONNEBEOBACHTET";
+    const PAIRS: [(char, char); 10] = [
+        ('A', 'V'),
+        ('B', 'S'),
+        ('C', 'G'),
+        ('D', 'L'),
+        ('F', 'U'),
+        ('H', 'Z'),
+        ('I', 'N'),
+        ('K', 'M'),
+        ('O', 'W'),
+        ('R', 'X'),
+    ];
+
+    let mut cables = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::II,
+        StandardWiring::IV,
+        StandardWiring::V,
+        Steckerbrett::try_from(&PAIRS[..]).unwrap(),
+    )
+    .unwrap();
+    cables.set_position_str("BLA").unwrap();
+
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::II,
+        StandardWiring::IV,
+        StandardWiring::V,
+        steckerbrett!("QY").unwrap(),
+    )
+    .unwrap();
+    enigma.set_position_str("BLA").unwrap();
+    enigma.set_uhr(Some(Uhr::new(&PAIRS, 0).unwrap()));
+    assert_eq!(enigma.uhr().unwrap().dial(), 0);
+
+    // At 00 the Uhr is the same as ordinary cables, and the cables of the machine itself are not used
+    let enc = cables.get_for_str(PLAIN, false, false).unwrap();
+    assert_eq!(enigma.get_for_str(PLAIN, false, false).unwrap(), enc);
+
+    for dial in 1..Uhr::POSITIONS {
+        enigma.set_uhr(Some(Uhr::new(&PAIRS, dial).unwrap()));
+        enigma.reset();
+
+        // The substitutions into and out of the rotors differ, but the machine as a whole stays reciprocal
+        let permutation = enigma.current_permutation();
+        assert!((0..26).all(|i| permutation[permutation[i] as usize] as usize == i));
+
+        let dialed = enigma.get_for_str(PLAIN, false, false).unwrap();
+        assert_ne!(dialed, enc, "dial {}", dial);
+        enigma.reset();
+        assert_eq!(enigma.get_for_str(&dialed, false, false).unwrap(), PLAIN);
+    }
+
+    // The fast path takes the same route
+    enigma.reset();
+    let dialed = enigma.get_for_str(PLAIN, false, false).unwrap();
+    enigma.reset();
+    let fast: String = {
+        let mut fast = enigma.prepare_fast();
+        PLAIN
+            .chars()
+            .map(|c| fast.encode_char(c).unwrap())
+            .collect()
+    };
+    assert_eq!(fast, dialed);
+
+    let mut other = enigma.clone();
+    other.set_uhr(Some(Uhr::new(&PAIRS, 1).unwrap()));
+    assert!(other != enigma);
+
+    // Removing the Uhr brings the cables back
+    enigma.set_uhr(None);
+    enigma.set_position_str("CCC").unwrap();
+    let mut plugged = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::II,
+        StandardWiring::IV,
+        StandardWiring::V,
+        steckerbrett!("QY").unwrap(),
+    )
+    .unwrap();
+    plugged.set_position_str("CCC").unwrap();
+    assert!(enigma == plugged);
+}
+
+#[test]
+fn test_enigma_standard_selection() {
+    use StandardWiring::*;
+
+    for (ukw, l, m, r) in [
+        (UKW_B, I, I, III),
+        (UKW_B, III, II, III),
+        (UKW_B, V, V, V),
+        (I, II, III, IV),
+        (UKW_B, UKW_C, II, III),
+        (UKW_B, Beta, II, III),
+    ] {
+        assert!(
+            matches!(
+                Enigma::standard(ukw, l, m, r, steckerbrett!()),
+                Err(EnigmaError::InvalidRotorSelection(_))
+            ),
+            "{} {} {} {}",
+            ukw,
+            l,
+            m,
+            r
+        );
+    }
+
+    assert!(Enigma::standard(UKW_C, VIII, I, VI, steckerbrett!()).is_ok());
+    assert!(Enigma::standard(UKW_A, III, II, I, steckerbrett!()).is_ok());
+}
+
+#[test]
+fn test_enigma_multiple_notches() {
+    let fast = Wiring::new(
+        Wiring::from(StandardWiring::I).as_template(),
+        &['S', 'c', 'K'],
+    )
+    .unwrap();
+    assert_eq!(fast.notches, vec![2, 10, 18]);
+
+    let mut enigma = Enigma::new(
+        StandardWiring::UKW_B.into(),
+        StandardWiring::II.into(),
+        StandardWiring::III.into(),
+        fast,
+        steckerbrett!(),
+    );
+
+    // The middle rotor steps whenever the right rotor leaves one of its three notches
+    let mut steps = Vec::new();
+    for key in 1..=26 {
+        let middle = enigma.get_position()[1];
+        enigma.get_for_char('A').unwrap();
+        if enigma.get_position()[1] != middle {
+            steps.push(key);
+        }
+    }
+    assert_eq!(steps, vec![3, 11, 19]);
+    assert_eq!(enigma.get_position_str(), "ADA");
+
+    let config = enigma.config();
+    assert_eq!(config.rotors[2].notches, vec!['C', 'K', 'S']);
+    assert!(Enigma::from_config(&config).unwrap() == enigma);
+}
+
+#[test]
+fn test_enigma_bytes() {
+    const PLAIN: &str = "Feindliche Infanterie-Kolonne beobachtet.\nAnfang Suedausgang Baerwalde, 3 km ostwaerts Neustadt!";
+
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::II,
+        StandardWiring::IV,
+        StandardWiring::V,
+        steckerbrett!("AV BS CG DL FU HZ IN KM OW RX").unwrap(),
+    )
+    .unwrap();
+    enigma.set_ring_str("BUL").unwrap();
+    enigma.set_position_str("BLA").unwrap();
+    let mut bytes = enigma.clone();
+
+    for (preserve_unsupported, preserve_case) in
+        [(false, false), (true, false), (false, true), (true, true)]
+    {
+        let expected = enigma
+            .get_for_str(PLAIN, preserve_unsupported, preserve_case)
+            .unwrap();
+        let enc = bytes
+            .get_for_bytes(PLAIN.as_bytes(), preserve_unsupported, preserve_case)
+            .unwrap();
+
+        assert_eq!(enc, expected.as_bytes());
+        assert_eq!(bytes.get_position_str(), enigma.get_position_str());
+    }
+
+    // Bytes above ASCII are not letters, even if they are in Latin-1
+    bytes.reset();
+    assert_eq!(
+        bytes
+            .get_for_bytes(&[0xC4, b'a', 0xFF], true, true)
+            .unwrap()
+            .len(),
+        3
+    );
+    assert_eq!(
+        bytes
+            .get_for_bytes(&[0xC4, b'a', 0xFF], false, true)
+            .unwrap()
+            .len(),
+        1
+    );
+    assert!(bytes.get_for_bytes(&[], true, true).unwrap().is_empty());
+}
+
+#[test]
+fn test_enigma_notch_state() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+
+    // Just before the notch of rotor III at 'V'
+    enigma.set_position_str("ADU").unwrap();
+    assert_eq!(enigma.notch_state(), [false, false, false]);
+    assert!(!enigma.will_double_step());
+
+    enigma.get_for_char('A').unwrap();
+    assert_eq!(enigma.get_position_str(), "ADV");
+    assert_eq!(enigma.notch_state(), [false, false, true]);
+    assert!(!enigma.will_double_step());
+
+    // Leaving the notch takes the middle rotor onto its own notch at 'E'
+    enigma.get_for_char('A').unwrap();
+    assert_eq!(enigma.get_position_str(), "AEW");
+    assert_eq!(enigma.notch_state(), [false, true, false]);
+    assert!(enigma.will_double_step());
+
+    // The ring settings don't move the notches
+    enigma.set_ring_str("BUL").unwrap();
+    assert_eq!(enigma.notch_state(), [false, true, false]);
+    enigma.set_position_str("QEV").unwrap();
+    assert_eq!(enigma.notch_state(), [true, true, true]);
+}
+
+#[test]
+fn test_enigma_display() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!("AQ FR SM").unwrap(),
+    )
+    .unwrap();
+    enigma.set_position_str("AAS").unwrap();
+    enigma.get_for_char('A').unwrap();
+
+    let display = enigma.to_string();
+    assert!(display.contains("pos=AAT"));
+    assert_eq!(
+        display,
+        "Enigma[UKW_B | I II III | pos=AAT | ring=AAA | stecker=3]"
+    );
+    assert_eq!(
+        format!("{:?}", enigma),
+        r#"Enigma { reflector: "UKW_B", rotors: ["I", "II", "III"], positions: "AAT", rings: "AAA", plugboard: "AQ FR MS", uhr: None }"#
+    );
+
+    let m4 = Enigma::m4(
+        StandardWiring::UKW_B_Thin,
+        StandardWiring::Beta,
+        StandardWiring::II,
+        StandardWiring::IV,
+        StandardWiring::I,
+        steckerbrett!(),
+    )
+    .unwrap();
+    assert_eq!(
+        m4.to_string(),
+        "Enigma[UKW_B_Thin | Beta II IV I | pos=AAAA | ring=AAAA | stecker=0]"
+    );
+
+    let mut custom = Enigma::new(
+        StandardWiring::UKW_C.into(),
+        Wiring::new(Wiring::from(StandardWiring::I).as_template(), &[]).unwrap(),
+        StandardWiring::V.into(),
+        StandardWiring::VI.into(),
+        steckerbrett!(),
+    );
+    custom.set_uhr(Some(
+        Uhr::new(
+            &[
+                ('A', 'B'),
+                ('C', 'D'),
+                ('E', 'F'),
+                ('G', 'H'),
+                ('I', 'J'),
+                ('K', 'L'),
+                ('M', 'N'),
+                ('O', 'P'),
+                ('Q', 'R'),
+                ('S', 'T'),
+            ],
+            7,
+        )
+        .unwrap(),
+    ));
+    assert_eq!(
+        custom.to_string(),
+        "Enigma[UKW_C | EKMFLGDQVZNTOWYHXUSPAIBRCJ V VI | pos=AAA | ring=AAA | stecker=0 | uhr=07]"
+    );
+}
+
+#[test]
+fn test_enigma_encode_iter() {
+    const PLAIN: &str = "Feindliche Infanterie-Kolonne beobachtet.";
+
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::II,
+        StandardWiring::IV,
+        StandardWiring::V,
+        steckerbrett!("AV BS CG DL FU HZ IN KM OW RX").unwrap(),
+    )
+    .unwrap();
+    enigma.set_ring_str("BUL").unwrap();
+    enigma.set_position_str("BLA").unwrap();
+    let mut iter = enigma.clone();
+
+    let expected = enigma.get_for_str(PLAIN, false, false).unwrap();
+    let enc: String = iter
+        .encode_iter(PLAIN.chars())
+        .filter_map(Result::ok)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    assert_eq!(enc, expected);
+    assert_eq!(iter.get_position_str(), enigma.get_position_str());
+
+    // One result per character, errors for everything that isn't a letter
+    iter.reset();
+    let results: Vec<_> = iter.encode_iter("Ab-c".chars()).collect();
+    assert_eq!(results.len(), 4);
+    assert!(results[0].as_ref().unwrap().is_ascii_uppercase());
+    assert!(results[1].as_ref().unwrap().is_ascii_lowercase());
+    assert!(matches!(
+        results[2],
+        Err(EnigmaError::UnsupportedCharacter('-'))
+    ));
+
+    // Nothing steps until the iterator is consumed
+    iter.reset();
+    let mut lazy = iter.encode_iter(PLAIN.chars());
+    lazy.next().unwrap().unwrap();
+    drop(lazy);
+    assert_eq!(iter.get_position_str(), "BLB");
+}
+
+#[test]
+fn test_enigma_settings() {
+    let mut enigma = Enigma::from_settings("B I II III AAA FCB AQ FR SM").unwrap();
+    let mut expected = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!("AQ FR SM").unwrap(),
+    )
+    .unwrap();
+    expected.set_position_str("FCB").unwrap();
+    assert!(enigma == expected);
+    assert_eq!(enigma.to_settings(), "B I II III AAA FCB AQ FR MS");
+
+    enigma.set_ring_str("BUL").unwrap();
+    enigma
+        .get_for_str("Feindliche Infanterie", false, false)
+        .unwrap();
+    assert!(Enigma::from_settings(&enigma.to_settings()).unwrap() == enigma);
+
+    let m4 = Enigma::from_settings("b_thin beta ii iv i aaav vjna").unwrap();
+    assert_eq!(m4.rotor_count(), 4);
+    assert_eq!(m4.to_settings(), "B_Thin Beta II IV I AAAV VJNA");
+    assert!(Enigma::from_settings(&m4.to_settings()).unwrap() == m4);
+
+    let plain = Enigma::from_settings("  UKW_C VIII VI V ZZZ ABC  ").unwrap();
+    assert_eq!(plain.to_settings(), "C VIII VI V ZZZ ABC");
+
+    for (settings, check) in [
+        ("", "InvalidSettings"),
+        ("B I II III AAA", "InvalidSettings"),
+        ("B_Thin Beta II IV AAAA AAAA", "InvalidWiringName"),
+        ("X I II III AAA AAA", "InvalidWiringName"),
+        ("B I II XI AAA AAA", "InvalidWiringName"),
+        ("I II III IV AAA AAA", "InvalidRotorSelection"),
+        ("B I I III AAA AAA", "InvalidRotorSelection"),
+        ("B_Thin I II IV Beta AAAA AAAA", "InvalidRotorSelection"),
+        ("B I II III AA AAA", "InvalidPositionLength"),
+        ("B I II III AAA A1A", "InvalidChar"),
+        ("B I II III AAA AAA AQ F", "InvalidPosition"),
+        ("B I II III AAA AAA AQ AR", "InvalidSteckerbrettString"),
+    ] {
+        let err = Enigma::from_settings(settings).err().unwrap();
+        assert!(
+            format!("{:?}", err).starts_with(check),
+            "{}: {:?}",
+            settings,
+            err
+        );
+    }
+}
+
+#[test]
+fn test_enigma_double_step() {
+    let mut enigma = Enigma::standard(
+        StandardWiring::UKW_B,
+        StandardWiring::I,
+        StandardWiring::II,
+        StandardWiring::III,
+        steckerbrett!(),
+    )
+    .unwrap();
+
+    // The middle rotor steps on two keypresses in a row, the second time taking the left rotor with it
+    enigma.set_position_str("ADT").unwrap();
+    let mut positions = Vec::new();
+    for _ in 0..5 {
+        enigma.get_for_char('A').unwrap();
+        positions.push(enigma.get_position_str());
+    }
+    assert_eq!(positions, vec!["ADU", "ADV", "AEW", "BFX", "BFY"]);
+
+    // Both pawls push the middle rotor at once, it still only moves by one
+    enigma.set_position_str("AEV").unwrap();
+    enigma.get_for_char('A').unwrap();
+    assert_eq!(enigma.get_position_str(), "BFW");
+
+    // The notch of the left rotor has nothing to push
+    enigma.set_position_str("QAT").unwrap();
+    for _ in 0..26 * 4 {
+        let left = enigma.get_position()[0];
+        let double_step = enigma.will_double_step();
+        enigma.get_for_char('A').unwrap();
+        assert_eq!(enigma.get_position()[0] != left, double_step);
+    }
+    assert_eq!(enigma.get_position_str(), "RFT");
+}
+
+#[test]
+fn test_steckerbrett_eq() {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    let s1 = steckerbrett!("AE IO ML").unwrap();
+    let s2 = steckerbrett!('A' => 'E', 'I' => 'O', 'M' => 'L').unwrap();
+    let s3 = steckerbrett!("LM OI EA").unwrap();
+
+    assert!(s1 == s2);
+    assert!(s1 == s3);
+    assert!(s1 != steckerbrett!("AE IO").unwrap());
+    assert_eq!(s1.len(), 3);
+    assert!(!s1.is_empty());
+
+    let hash = |s: &Steckerbrett| {
+        let mut hasher = DefaultHasher::new();
+        s.hash(&mut hasher);
+        hasher.finish()
+    };
+    assert_eq!(hash(&s1), hash(&s3));
+
+    let empty = steckerbrett!();
+    assert_eq!(empty.len(), 0);
+    assert!(empty.is_empty());
+    assert!(empty == Steckerbrett::default());
+
+    let mut c = EnigmaChar {
+        internal: 4,
+        uppercase: true,
+    };
+    s1.get(&mut c);
+    assert_eq!(c.internal, 0);
+
+    // Values outside of the alphabet pass through
+    c.internal = 200;
+    s1.get(&mut c);
+    assert_eq!(c.internal, 200);
+}
+
+#[test]
+fn test_key_sheet() {
+    let sheet = KeySheet::parse(
+        "B I II III AAA AAA AQ FR SM
+
+         C IV V VI BQR AAA DL FU HZ",
+    )
+    .unwrap();
+    assert_eq!(sheet.days(), vec![1, 2]);
+
+    let mut day1 = sheet.for_day(1).unwrap();
+    let mut day2 = sheet.for_day(2).unwrap();
+    assert!(day1 == Enigma::from_settings("B I II III AAA AAA AQ FR SM").unwrap());
+    assert_eq!(sheet.get(2).unwrap().rings, "BQR");
+
+    let enc1 = day1
+        .get_for_str("Angriff im Morgengrauen", true, true)
+        .unwrap();
+    let enc2 = day2
+        .get_for_str("Angriff im Morgengrauen", true, true)
+        .unwrap();
+    assert_ne!(enc1, enc2);
+
+    for day in [0, 3, 31, 32] {
+        assert!(matches!(
+            sheet.for_day(day),
+            Err(EnigmaError::InvalidDay(d)) if d == day
+        ));
+    }
+
+    // Printed sheets start with the last day of the month
+    let sheet = KeySheet::parse(
+        "31 B II IV V BUL AAA AV BS CG DL FU HZ IN KM OW RX
+         30 C I VIII III FNZ AAA
+         15 B III I II AAA AAA",
+    )
+    .unwrap();
+    assert_eq!(sheet.days(), vec![15, 30, 31]);
+    assert!(matches!(
+        KeySheet::parse("31 B I II III AAA AAA\nB I II III AAA AAA"),
+        Err(EnigmaError::InvalidDay(32))
+    ));
+    assert!(matches!(
+        KeySheet::parse("2 B I II III AAA AAA\nB I II III AAA AAA\n3 B I II III AAA AAA"),
+        Err(EnigmaError::InvalidDay(3))
+    ));
+    assert!(KeySheet::parse("0 B I II III AAA AAA").is_err());
+    assert!(KeySheet::parse("B I II III AAA").is_err());
+    assert!(KeySheet::parse("").unwrap().days().is_empty());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_batch_encode() {
+    fn assert_shareable<T: Clone + Send + Sync>() {}
+    assert_shareable::<Enigma>();
+
+    let mut template =
+        Enigma::from_settings("B II IV V BUL AAA AV BS CG DL FU HZ IN KM OW RX").unwrap();
+    template.set_uhr(Some(
+        Uhr::new(
+            &[
+                ('A', 'B'),
+                ('C', 'D'),
+                ('E', 'F'),
+                ('G', 'H'),
+                ('I', 'J'),
+                ('K', 'L'),
+                ('M', 'N'),
+                ('O', 'P'),
+                ('Q', 'R'),
+                ('S', 'T'),
+            ],
+            13,
+        )
+        .unwrap(),
+    ));
+    template.set_position_str("ZZZ").unwrap();
+    let before = template.clone();
+
+    let positions: Vec<String> = (0..200)
+        .map(|i| {
+            [i / 676, i / 26 % 26, i % 26]
+                .iter()
+                .map(|p| (b'A' + (*p * 7 % 26) as u8) as char)
+                .collect()
+        })
+        .collect();
+    let positions: Vec<&str> = positions.iter().map(String::as_str).collect();
+    let plain = "Feindliche Infanterie-Kolonne beobachtet.";
+
+    let serial: Vec<String> = positions
+        .iter()
+        .map(|p| {
+            let mut enigma = template.clone();
+            enigma.set_position_str(p).unwrap();
+            enigma.get_for_str(plain, false, false).unwrap()
+        })
+        .collect();
+
+    assert_eq!(
+        enigma::batch_encode(&template, &positions, plain).unwrap(),
+        serial
+    );
+    assert!(template == before);
+
+    assert!(enigma::batch_encode(&template, &[], plain)
+        .unwrap()
+        .is_empty());
+    assert!(matches!(
+        enigma::batch_encode(&template, &["AAA", "AA", "1AA"], plain),
+        Err(EnigmaError::InvalidPositionLength(_, 3))
+    ));
+}
+
+#[test]
+fn test_enigma_ukw_d() {
+    let pairs = [
+        ('A', 'C'),
+        ('B', 'Z'),
+        ('D', 'H'),
+        ('E', 'M'),
+        ('F', 'T'),
+        ('G', 'O'),
+        ('I', 'U'),
+        ('K', 'X'),
+        ('L', 'R'),
+        ('N', 'P'),
+        ('Q', 'V'),
+        ('S', 'W'),
+    ];
+    let ukw_d = Wiring::reflector_from_pairs(&pairs).unwrap();
+    assert!(ukw_d.validate_involution().is_ok());
+    assert!(ukw_d.notches.is_empty());
+
+    let lower: Vec<(char, char)> = pairs
+        .iter()
+        .map(|(a, b)| (a.to_ascii_lowercase(), *b))
+        .collect();
+    assert!(Wiring::reflector_from_pairs(&lower).unwrap() == ukw_d);
+
+    let mut enigma = Enigma::new(
+        ukw_d,
+        StandardWiring::I.into(),
+        StandardWiring::II.into(),
+        StandardWiring::III.into(),
+        steckerbrett!("AV BS CG DL FU HZ IN KM OW RX").unwrap(),
+    );
+    enigma.set_position_str("QEV").unwrap();
+    let plain = "Feindliche Infanterie-Kolonne beobachtet.";
+    let enc = enigma.get_for_str(plain, true, true).unwrap();
+    assert_ne!(enc, plain);
+
+    enigma.reset();
+    assert_eq!(enigma.get_for_str(&enc, true, true).unwrap(), plain);
+
+    let with = |i: usize, pair: (char, char)| {
+        let mut p = pairs.to_vec();
+        p[i] = pair;
+        Wiring::reflector_from_pairs(&p)
+    };
+    for invalid in [
+        Wiring::reflector_from_pairs(&pairs[..11]),
+        Wiring::reflector_from_pairs(&[&pairs[..], &[('J', 'Y')]].concat()),
+        with(0, ('J', 'C')),
+        with(0, ('A', 'Y')),
+        with(0, ('A', 'A')),
+        with(0, ('a', 'B')),
+        with(11, ('S', 'S')),
+    ] {
+        assert!(matches!(invalid, Err(EnigmaError::InvalidReflector(_))));
+    }
+    assert!(matches!(
+        with(0, ('A', '1')),
+        Err(EnigmaError::InvalidChar('1'))
+    ));
+}
+
+#[test]
+fn test_enigma_encode_into() {
+    let messages = [
+        "Feindliche Infanterie-Kolonne beobachtet.",
+        "",
+        "Anfang Suedausgang Baerwalde, 3 km ostwaerts Neustadt!",
+        "ÄÖÜ",
+    ];
+    let mut enigma =
+        Enigma::from_settings("B II IV V BUL BLA AV BS CG DL FU HZ IN KM OW RX").unwrap();
+    let mut into = enigma.clone();
+
+    for (preserve_unsupported, preserve_case) in
+        [(false, false), (true, false), (false, true), (true, true)]
+    {
+        let mut out = String::new();
+        for message in messages {
+            out.clear();
+            into.encode_into(message, &mut out, preserve_unsupported, preserve_case)
+                .unwrap();
+
+            assert_eq!(
+                out,
+                enigma
+                    .get_for_str(message, preserve_unsupported, preserve_case)
+                    .unwrap()
+            );
+            assert_eq!(into.get_position_str(), enigma.get_position_str());
+        }
+    }
+
+    // The buffer is appended to, not replaced
+    enigma.reset();
+    let mut out = String::from("KR ");
+    enigma.encode_into("Ab", &mut out, false, true).unwrap();
+    enigma.encode_into("c", &mut out, false, false).unwrap();
+
+    enigma.reset();
+    let expected = enigma.get_for_str("Ab", false, true).unwrap()
+        + &enigma.get_for_str("C", false, true).unwrap();
+    assert_eq!(out, format!("KR {}", expected));
+}
+
+#[test]
+fn test_enigma_set_rotors() {
+    let mut enigma = Enigma::from_settings("B I II III AAA AAA AQ FR SM").unwrap();
+    let plain = "Feindliche Infanterie-Kolonne beobachtet.";
+    let with_iii = enigma.get_for_str(plain, false, false).unwrap();
+
+    enigma.set_rotors(
+        StandardWiring::I.into(),
+        StandardWiring::II.into(),
+        StandardWiring::IV.into(),
+    );
+    assert_eq!(enigma.get_position_str(), "AAA");
+    assert_eq!(enigma.get_ring_str(), "AAA");
+    assert_eq!(enigma.to_settings(), "B I II IV AAA AAA AQ FR MS");
+    assert_eq!(
+        enigma.config().plugboard,
+        steckerbrett!("AQ FR SM").unwrap().pairs()
+    );
+
+    let with_iv = enigma.get_for_str(plain, false, false).unwrap();
+    assert_ne!(with_iii, with_iv);
+    assert!(enigma.rewind(plain.len() + 1).is_err());
+    enigma.reset();
+    assert_eq!(
+        enigma.get_for_str(&with_iv, false, false).unwrap(),
+        "FEINDLICHEINFANTERIEKOLONNEBEOBACHTET"
+    );
+
+    enigma.set_reflector(StandardWiring::UKW_C.into());
+    enigma.reset();
+    assert_ne!(enigma.get_for_str(plain, false, false).unwrap(), with_iv);
+    enigma.reset();
+    assert_eq!(enigma.to_settings(), "C I II IV AAA AAA AQ FR MS");
+
+    // The Greek rotor of an M4 stays in place
+    let mut m4 = Enigma::from_settings("B_Thin Beta II IV I BAAA VJNA").unwrap();
+    m4.set_rotors(
+        StandardWiring::III.into(),
+        StandardWiring::V.into(),
+        StandardWiring::VI.into(),
+    );
+    assert_eq!(m4.to_settings(), "B_Thin Beta III V VI BAAA VAAA");
+    let enc = m4.get_for_str(plain, false, false).unwrap();
+    m4.reset();
+    assert_eq!(m4.get_position_str(), "VAAA");
+    assert_eq!(
+        m4.get_for_str(&enc, false, false).unwrap(),
+        "FEINDLICHEINFANTERIEKOLONNEBEOBACHTET"
+    );
+}
+
+#[test]
+fn test_enigma_numeric_settings() {
+    let mut numeric =
+        Enigma::from_settings("B II IV V AAA AAA AV BS CG DL FU HZ IN KM OW RX").unwrap();
+    let mut letters = numeric.clone();
+
+    numeric.set_ring_numeric(2, 1, 1).unwrap();
+    letters.set_ring_str("BAA").unwrap();
+    assert!(numeric == letters);
+    assert_eq!(numeric.get_ring_numeric(), [2, 1, 1]);
+
+    numeric.set_ring_numeric(2, 21, 12).unwrap();
+    numeric.set_position_numeric(2, 12, 1).unwrap();
+    letters.set_ring_str("BUL").unwrap();
+    letters.set_position_str("BLA").unwrap();
+    assert!(numeric == letters);
+    assert_eq!(numeric.get_position_numeric(), [2, 12, 1]);
+
+    let plain = "Feindliche Infanterie-Kolonne beobachtet.";
+    assert_eq!(
+        numeric.get_for_str(plain, false, false).unwrap(),
+        letters.get_for_str(plain, false, false).unwrap()
+    );
+    numeric.reset();
+    assert_eq!(numeric.get_position_str(), "BLA");
+
+    numeric.set_ring_numeric(26, 26, 26).unwrap();
+    numeric.set_position_numeric(26, 1, 26).unwrap();
+    assert_eq!(numeric.get_ring_str(), "ZZZ");
+    assert_eq!(numeric.get_position_str(), "ZAZ");
+
+    for (l, m, r, n) in [(0, 1, 1, 0), (1, 27, 1, 27), (1, 1, 255, 255)] {
+        assert!(matches!(
+            numeric.set_ring_numeric(l, m, r),
+            Err(EnigmaError::InvalidNumber(e)) if e == n
+        ));
+        assert!(matches!(
+            numeric.set_position_numeric(l, m, r),
+            Err(EnigmaError::InvalidNumber(e)) if e == n
+        ));
+    }
+    assert_eq!(numeric.get_ring_str(), "ZZZ");
+    assert_eq!(numeric.get_position_str(), "ZAZ");
+
+    // The Greek rotor of an M4 keeps its setting
+    let mut m4 = Enigma::from_settings("B_Thin Beta II IV I CAAA VJNA").unwrap();
+    m4.set_ring_numeric(1, 1, 22).unwrap();
+    assert_eq!(m4.get_ring_str(), "CAAV");
+    assert_eq!(m4.get_ring_numeric(), [1, 1, 22]);
+}
+
+#[test]
+fn test_enigma_advance() {
+    let mut encoded =
+        Enigma::from_settings("B II IV V BUL ADT AV BS CG DL FU HZ IN KM OW RX").unwrap();
+    let mut advanced = encoded.clone();
+    let plain = "FeindlicheInfanterieKolonneBeobachtetAnfangSuedausgangBaerwalde".repeat(20);
+
+    let mut total = 0;
+    for n in [0, 1, 2, 25, 26, 27, 676, 1000] {
+        let before = advanced.clone();
+        let peeked = advanced.peek_position(n);
+        assert!(advanced == before);
+
+        advanced.advance(n);
+        encoded.get_for_str(&plain[..n], false, false).unwrap();
+        total += n;
+
+        assert_eq!(advanced.get_position_str(), encoded.get_position_str());
+        assert_eq!(advanced.get_position(), peeked);
+    }
+
+    // Advancing can be undone like encoding
+    advanced.rewind(total).unwrap();
+    assert_eq!(advanced.get_position_str(), "ADT");
+    assert!(advanced.rewind(1).is_err());
+
+    // Positions after the double-step
+    let enigma = Enigma::from_settings("B I II III AAA ADT").unwrap();
+    assert_eq!(enigma.peek_position(3), ['A', 'E', 'W']);
+    assert_eq!(enigma.peek_position(4), ['B', 'F', 'X']);
+}
+
+#[test]
+fn test_enigma_transliterate() {
+    use enigma::preprocess::{transliterate, transliterate_with, Umlauts};
+
+    let mut enigma =
+        Enigma::from_settings("B II IV V BUL BLA AV BS CG DL FU HZ IN KM OW RX").unwrap();
+
+    for plain in [
+        transliterate("schön"),
+        transliterate_with("schön", Umlauts::Fold),
+        transliterate("Grüße aus Besançon, Señor Müller!"),
+    ] {
+        assert!(plain.is_ascii());
+
+        enigma.reset();
+        let enc = enigma.get_for_str(&plain, true, true).unwrap();
+        enigma.reset();
+        assert_eq!(enigma.get_for_str(&enc, true, true).unwrap(), plain);
+    }
+
+    // Without transliteration the umlaut is lost
+    enigma.reset();
+    let enc = enigma.get_for_str("schön", false, true).unwrap();
+    enigma.reset();
+    assert_eq!(enigma.get_for_str(&enc, false, true).unwrap(), "schn");
+
+    // Letters missing from the table are left to the machine, which drops them instead of keying a wrong letter
+    assert_eq!(transliterate("Średnio, Erdős"), "Średnio, Erdős");
+    enigma.reset();
+    let enc = enigma
+        .get_for_str(&transliterate("Średnio, Erdős"), false, true)
+        .unwrap();
+    enigma.reset();
+    assert_eq!(enigma.get_for_str(&enc, false, true).unwrap(), "rednioErds");
+}
+
+#[test]
+fn test_enigma_encodes_to_self() {
+    let mut enigma =
+        Enigma::from_settings("B II IV V BUL AAA AV BS CG DL FU HZ IN KM OW RX").unwrap();
+    let sample = "Feindliche Infanterie-Kolonne beobachtet.";
+
+    for _ in 0..26 * 26 * 3 {
+        assert_eq!(enigma.encodes_to_self(), None);
+        enigma.advance(1);
+    }
+    assert!(enigma.is_reciprocal(sample));
+    assert_eq!(enigma.get_position_str(), "AAA");
+
+    // A and Y of UKW B wired straight through: every keypress that reaches them comes back to itself
+    let ukw_b = Wiring::from(StandardWiring::UKW_B).as_template();
+    let mut straight = ukw_b;
+    straight[0] = 'A';
+    straight[(b'Y' - b'A') as usize] = 'Y';
+    enigma.set_reflector(Wiring::new(straight, &[]).unwrap());
+    assert!(enigma.encodes_to_self().is_some());
+    assert!(enigma.is_reciprocal(sample));
+
+    // A, Y, R and B of UKW B wired in a loop instead of in pairs: no letter maps to itself, but the way back differs
+    let mut looped = ukw_b;
+    looped[0] = 'Y';
+    looped[(b'Y' - b'A') as usize] = 'R';
+    looped[(b'R' - b'A') as usize] = 'B';
+    looped[1] = 'A';
+    enigma.set_reflector(Wiring::new(looped, &[]).unwrap());
+    for _ in 0..26 * 26 {
+        assert_eq!(enigma.encodes_to_self(), None);
+        enigma.advance(1);
+    }
+    assert!(!enigma.is_reciprocal(sample));
+    assert!(enigma.is_reciprocal(""));
+}
+
+#[test]
+fn test_enigma_rotor_cycle() {
+    let mut enigma = Enigma::from_settings("B I II III AAA AAA AQ FR SM").unwrap();
+    assert_eq!(enigma.chars_encoded(), 0);
+    assert_eq!(enigma.remaining_before_repeat(), Some(16900));
+
+    enigma
+        .get_for_str("Feind in Sicht, 3 Zerstoerer!", true, true)
+        .unwrap();
+    assert_eq!(enigma.chars_encoded(), 22);
+    enigma.get_for_char('x').unwrap();
+    assert!(enigma.get_for_char('3').is_err());
+    assert_eq!(enigma.chars_encoded(), 23);
+    enigma.advance(2);
+    assert_eq!(enigma.chars_encoded(), 25);
+
+    // Every position on the cycle comes back after the same number of keypresses
+    assert_eq!(enigma.remaining_before_repeat(), Some(16900));
+    let position = enigma.get_position_str();
+    enigma.advance(16899);
+    assert_ne!(enigma.get_position_str(), position);
+    enigma.advance(1);
+    assert_eq!(enigma.get_position_str(), position);
+
+    enigma.reset();
+    assert_eq!(enigma.chars_encoded(), 0);
+    enigma.get_for_str("abc", false, false).unwrap();
+    enigma.set_position_str("ADU").unwrap();
+    assert_eq!(enigma.chars_encoded(), 0);
+
+    // The middle rotor on its notch only comes right after the right rotor passed its own
+    assert_eq!(enigma.remaining_before_repeat(), Some(16900));
+    enigma.set_position_str("AEW").unwrap();
+    assert_eq!(enigma.remaining_before_repeat(), Some(16900));
+    enigma.set_position_str("AEV").unwrap();
+    assert_eq!(enigma.remaining_before_repeat(), None);
+    assert_eq!(enigma.get_position_str(), "AEV");
+
+    // The Greek rotor of an M4 doesn't step, two notches on VI, VII and VIII shorten the cycle
+    let m4 = Enigma::from_settings("B_Thin Beta I II III AAAA AAAA").unwrap();
+    assert_eq!(m4.remaining_before_repeat(), Some(16900));
+    let navy = Enigma::from_settings("B I II VI AAA AAA").unwrap();
+    assert_eq!(navy.remaining_before_repeat(), Some(26 * 25 * 13));
+}
+
+#[test]
+fn test_enigma_encode_with_trace() {
+    const PLAIN: &str = "Feindliche Infanterie-Kolonne beobachtet. Anfang Suedausgang Baerwalde!";
+
+    let mut enigma = Enigma::from_settings("B I II III AAA ADS AQ FR SM").unwrap();
+    let mut manual = enigma.clone();
+    let mut plain = enigma.clone();
+
+    for preserve_unsupported in [false, true] {
+        enigma.reset();
+        manual.reset();
+        plain.reset();
+
+        let trace = enigma
+            .encode_with_trace(PLAIN, preserve_unsupported)
+            .unwrap();
+        let expected = plain
+            .get_for_str(PLAIN, preserve_unsupported, true)
+            .unwrap();
+        assert_eq!(trace.iter().map(|t| t.2).collect::<String>(), expected);
+        assert_eq!(enigma.get_position_str(), plain.get_position_str());
+
+        let inputs: Vec<char> = PLAIN
+            .chars()
+            .filter(|c| preserve_unsupported || c.is_ascii_alphabetic())
+            .collect();
+        assert_eq!(trace.len(), inputs.len());
+
+        for ((positions, input, output), c) in trace.iter().zip(inputs) {
+            assert_eq!(*input, c);
+            match manual.get_for_char(c) {
+                Ok(o) => assert_eq!(*output, o),
+                Err(_) => assert_eq!(output, input),
+            }
+            assert_eq!(
+                positions.iter().collect::<String>(),
+                manual.get_position_str()
+            );
+        }
+    }
+
+    // The double-step shows up in consecutive entries
+    enigma.set_position_str("ADT").unwrap();
+    let positions: Vec<String> = enigma
+        .encode_with_trace("aaaa", false)
+        .unwrap()
+        .iter()
+        .map(|t| t.0.iter().collect())
+        .collect();
+    assert_eq!(positions, vec!["ADU", "ADV", "AEW", "BFX"]);
+    assert!(enigma.encode_with_trace("", true).unwrap().is_empty());
+}
+
+#[test]
+fn test_enigma_m4_selection() {
+    use StandardWiring::*;
+
+    assert!(Enigma::m4(UKW_B_Thin, Beta, II, IV, I, steckerbrett!()).is_ok());
+    for (ukw, greek, l, m, r) in [
+        (UKW_B, I, I, I, I),
+        (I, Beta, II, IV, I),
+        (UKW_B_Thin, I, II, IV, V),
+        (UKW_B_Thin, Beta, Gamma, IV, I),
+        (UKW_B_Thin, Beta, II, IV, IV),
+        (UKW_C_Thin, Gamma, II, UKW_A, I),
+    ] {
+        let selection = format!("{} {} {} {} {}", ukw, greek, l, m, r);
+        assert!(matches!(
+            Enigma::m4(ukw, greek, l, m, r, steckerbrett!()),
+            Err(EnigmaError::InvalidRotorSelection(s)) if s == selection
+        ));
+    }
+
+    // The same selections are rejected when set by name
+    let mut m4 = Enigma::m4(UKW_B_Thin, Beta, II, IV, I, steckerbrett!()).unwrap();
+    for names in [
+        "I II IV V",
+        "Beta Gamma IV I",
+        "Beta II IV IV",
+        "Gamma II IV",
+    ] {
+        assert!(matches!(
+            m4.set_rotors_by_name(names),
+            Err(EnigmaError::InvalidRotorSelection(n)) if n == names
+        ));
+    }
+    m4.set_ring_str("BCDE").unwrap();
+    m4.set_position_str("FGHI").unwrap();
+    m4.set_rotors_by_name("Gamma VIII VII VI").unwrap();
+    assert_eq!(m4.get_ring_str(), "AAAA");
+    assert_eq!(m4.get_position_str(), "AAAA");
+    assert_eq!(m4.to_settings(), "B_Thin Gamma VIII VII VI AAAA AAAA");
+}
+
+#[test]
+fn test_enigma_uhr_config() {
+    const PAIRS: [(char, char); 10] = [
+        ('A', 'V'),
+        ('B', 'S'),
+        ('C', 'G'),
+        ('D', 'L'),
+        ('F', 'U'),
+        ('H', 'Z'),
+        ('I', 'N'),
+        ('K', 'M'),
+        ('O', 'W'),
+        ('R', 'X'),
+    ];
+
+    let mut enigma = Enigma::from_settings("B II IV V BUL BLA QY").unwrap();
+    assert!(matches!(
+        enigma.set_uhr_dial(5),
+        Err(EnigmaError::MissingUhr)
+    ));
+
+    enigma.set_uhr(Some(Uhr::new(&PAIRS, 0).unwrap()));
+    enigma.set_uhr_dial(27).unwrap();
+    assert_eq!(enigma.uhr().unwrap().dial(), 27);
+    assert!(matches!(
+        enigma.set_uhr_dial(40),
+        Err(EnigmaError::InvalidUhrDial(40))
+    ));
+    assert_eq!(enigma.uhr().unwrap().dial(), 27);
+
+    let config = enigma.config();
+    let uhr = config.uhr.as_ref().unwrap();
+    assert_eq!(uhr.pairs, PAIRS.to_vec());
+    assert_eq!(uhr.dial, 27);
+
+    let mut copy = Enigma::from_config(&config).unwrap();
+    assert!(copy == enigma);
+    assert_eq!(
+        copy.get_for_str("FEINDLICHEINFANTERIEKOLONNE", false, false)
+            .unwrap(),
+        enigma
+            .get_for_str("FEINDLICHEINFANTERIEKOLONNE", false, false)
+            .unwrap()
+    );
+
+    // The cables of the machine itself are written before the Uhr
+    enigma.reset();
+    let settings = enigma.to_settings();
+    assert_eq!(
+        settings,
+        "B II IV V BUL BLA QY uhr=27 AV BS CG DL FU HZ IN KM OW RX"
+    );
+    let parsed = Enigma::from_settings(&settings).unwrap();
+    assert!(parsed == enigma);
+    assert_eq!(parsed.config(), enigma.config());
+
+    let sheet = KeySheet::parse(&format!("3 {}", settings)).unwrap();
+    assert_eq!(sheet.get(3), Some(&enigma.config()));
+    assert!(sheet.for_day(3).unwrap() == enigma);
+
+    for settings in [
+        "B II IV V BUL BLA uhr=40 AV BS CG DL FU HZ IN KM OW RX",
+        "B II IV V BUL BLA uhr=x AV BS CG DL FU HZ IN KM OW RX",
+        "B II IV V BUL BLA uhr=01 AV BS CG DL FU HZ IN KM OW",
+        "B II IV V BUL BLA uhr=01 AV BS CG DL FU HZ IN KM OW RXY",
+    ] {
+        assert!(Enigma::from_settings(settings).is_err(), "{}", settings);
+    }
+
+    let mut invalid = config;
+    invalid.uhr.as_mut().unwrap().dial = 40;
+    assert!(matches!(
+        Enigma::from_config(&invalid),
+        Err(EnigmaError::InvalidUhrDial(40))
+    ));
+}
+
+#[test]
+fn test_keysheet_generate() {
+    let mut state = 1u64;
+    let mut pick = |n: usize| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) as usize % n
+    };
+
+    let sheet = KeySheet::generate(&mut pick, 31).unwrap();
+    assert_eq!(sheet.days(), (1..=31).collect::<Vec<u8>>());
+    for day in sheet.days() {
+        let enigma = Enigma::from_config(sheet.get(day).unwrap()).unwrap();
+        assert_eq!(&enigma.config(), sheet.get(day).unwrap());
+    }
+
+    let text = sheet.to_string();
+    assert_eq!(text.lines().count(), 31);
+    assert!(text.starts_with("31 "));
+    assert_eq!(KeySheet::parse(&text).unwrap(), sheet);
+
+    let partial = KeySheet::generate(&mut pick, 3).unwrap();
+    assert_eq!(partial.days(), vec![1, 2, 3]);
+    assert_eq!(KeySheet::parse(&partial.to_string()).unwrap(), partial);
+    assert_eq!(KeySheet::generate(&mut pick, 0).unwrap(), KeySheet::new());
+    assert_eq!(KeySheet::new().to_string(), "");
+
+    assert!(matches!(
+        KeySheet::generate(&mut pick, 32),
+        Err(EnigmaError::InvalidDay(32))
+    ));
+}
+
+#[test]
+fn test_keysheet_set() {
+    let mut sheet = KeySheet::new();
+
+    let mut m3 = Enigma::from_settings("C I VIII III FNZ AAA BQ CR")
+        .unwrap()
+        .config();
+    m3.plugboard = vec![('R', 'C'), ('Q', 'B')];
+    sheet.set(1, m3).unwrap();
+    assert_eq!(
+        sheet.get(1).unwrap().plugboard,
+        vec![('B', 'Q'), ('C', 'R')]
+    );
+
+    sheet
+        .set(
+            2,
+            Enigma::from_settings("B_Thin Beta II IV I AAAA VJNA")
+                .unwrap()
+                .config(),
+        )
+        .unwrap();
+    sheet
+        .set(
+            3,
+            Enigma::from_settings("B II IV V BUL BLA uhr=27 AV BS CG DL FU HZ IN KM OW RX")
+                .unwrap()
+                .config(),
+        )
+        .unwrap();
+    assert_eq!(KeySheet::parse(&sheet.to_string()).unwrap(), sheet);
+
+    // Custom wirings and machines `from_settings()` can't describe don't fit on a sheet
+    let mut custom = Enigma::from_settings("B I II III AAA AAA")
+        .unwrap()
+        .config();
+    custom.rotors[0].name = None;
+    custom.rotors[0].notches = vec!['C'];
+    assert!(matches!(
+        sheet.set(4, custom),
+        Err(EnigmaError::InvalidSettings(_))
+    ));
+
+    let wide = Enigma::with_rotors(
+        StandardWiring::UKW_B.into(),
+        vec![
+            StandardWiring::V.into(),
+            StandardWiring::I.into(),
+            StandardWiring::II.into(),
+            StandardWiring::III.into(),
+        ],
+        steckerbrett!(),
+    )
+    .unwrap();
+    assert!(matches!(
+        sheet.set(4, wide.config()),
+        Err(EnigmaError::InvalidSettings(_))
+    ));
+
+    let mut broken = Enigma::from_settings("B I II III AAA AAA")
+        .unwrap()
+        .config();
+    broken.positions = "AA".to_owned();
+    assert!(matches!(
+        sheet.set(4, broken),
+        Err(EnigmaError::InvalidPositionLength(_, 3))
+    ));
+    assert!(sheet.get(4).is_none());
+    assert_eq!(sheet.days(), vec![1, 2, 3]);
+}